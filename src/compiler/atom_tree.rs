@@ -13,10 +13,17 @@ pub struct AtomRoot {
 
 #[derive(Debug, Clone)]
 pub enum ValueAction {
-    Output(Vec<String>, Vec<AtomTree>),
+    Output(Vec<String>, Vec<OutputValue>),
     Restriction(AtomType)
 }
 
+#[derive(Debug, Clone)]
+pub enum OutputValue {
+    Label(AtomTree),
+    //The bits of an enum, printed as the variant they encode. The variants are given with their encoding
+    Enum { name: String, variants: Vec<(String, Vec<bool>)>, bits: Vec<AtomTree> }
+}
+
 impl AtomRoot {
     pub fn inline_all(&mut self) {
         let mut new_definitions = HashMap::new();
@@ -7,7 +7,7 @@ use std::collections::{HashMap, HashSet};
 
 use enum_as_inner::EnumAsInner;
 
-use crate::compiler::{atom_tree::{AtomRoot, AtomTree, ValueAction}, token::AtomType};
+use crate::compiler::{atom_tree::{AtomRoot, AtomTree, OutputValue, ValueAction}, token::AtomType};
 
 pub struct AtomTreeCompiler {
     true_node: usize,
@@ -16,7 +16,18 @@ pub struct AtomTreeCompiler {
     tree: AtomRoot,
     nodes: Vec<Node>,
     //Maps a variable id in the Atom tree to its corrosponding node.
-    variable_mappings: HashMap<usize, usize>
+    variable_mappings: HashMap<usize, usize>,
+    outputs: Vec<String>,
+    unsolvable_restrictions: bool,
+}
+
+///The compiled graph along with the side results of running the IR.
+pub struct CompiledGraph {
+    pub nodes: Vec<Node>,
+    //Text of every output statement whose condition was met
+    pub outputs: Vec<String>,
+    //Whether a node ended up without any allowed label
+    pub unsolvable_restrictions: bool,
 }
 impl AtomTreeCompiler {
     pub fn new(tree: AtomRoot) -> Self {
@@ -26,12 +37,14 @@ impl AtomTreeCompiler {
             neutral_node: 0,
             nodes: vec![],
             variable_mappings: HashMap::new(),
+            outputs: vec![],
+            unsolvable_restrictions: false,
 
             tree
         }
     }
 
-    pub fn compile(mut self) -> Vec<Node> {
+    pub fn compile(mut self) -> CompiledGraph {
 
         
         let true_node = Node::new(&mut self, Label::True);
@@ -59,19 +72,38 @@ impl AtomTreeCompiler {
                     if !restriction_node.label.is_true() {
                         continue;
                     }
+                    let mut output = String::new();
                     for i in 0..format_string.len() {
                         let s = &format_string[i];
                         if values.len() <= i {
-                            print!("{s}");
+                            output.push_str(s);
                             continue;
                         }
-                        let v = &values[i];
-                        let display_node = self.compile_tree(v);
-                        let display_node = &self.nodes[display_node];
-                        print!("{s}{:?}", display_node.label);
+                        output.push_str(s);
+                        match &values[i] {
+                            OutputValue::Label(v) => {
+                                let display_node = self.compile_tree(v);
+                                output.push_str(&format!("{:?}", self.nodes[display_node].label));
+                            }
+                            OutputValue::Enum { name, variants, bits } => {
+                                let labels: Vec<Label> = bits.iter().map(|bit| {
+                                    let node = self.compile_tree(bit);
+                                    self.nodes[node].label
+                                }).collect();
+                                let variant = variants.iter().find(|(_, encoding)| labels.len() == encoding.len() && labels.iter().zip(encoding).all(|(label, bit)| if *bit { label.is_true() } else { label.is_false() }));
+                                match variant {
+                                    Some((variant, _)) => output.push_str(&format!("{name}.{variant}")),
+                                    //Unknown bits, or bits that don't encode a variant
+                                    None => {
+                                        let labels: Vec<String> = labels.iter().map(|label| format!("{label:?}, ")).collect();
+                                        output.push_str(&format!("{name}({})", labels.concat()));
+                                    }
+                                }
+                            }
+                        }
                     }
 
-                    println!()
+                    self.outputs.push(output);
                 }
                 _ => todo!()
             }
@@ -82,7 +114,11 @@ impl AtomTreeCompiler {
             Node::connect_with_whitelist(i, &mut self);
         }
 
-        self.nodes
+        CompiledGraph {
+            nodes: self.nodes,
+            outputs: self.outputs,
+            unsolvable_restrictions: self.unsolvable_restrictions,
+        }
 
     }
     fn compile_var(&mut self, var_id: usize) -> usize {
@@ -236,7 +272,7 @@ impl Node {
             }
         }
         if union.is_empty() {
-            println!("Node restrictions made graph unsolvable.")
+            compiler.unsolvable_restrictions = true;
         }
         *node_whitelist = union;
     }
@@ -252,7 +288,7 @@ impl Node {
                 Label::False => Node::connect(v, compiler.false_node, compiler),
                 Label::True => Node::connect(v, compiler.true_node, compiler),
                 Label::Neutral => Node::connect(v, compiler.neutral_node, compiler),
-                _ => {println!("Unexpected label")}
+                Label::Null => {}
             }
         }
         
@@ -261,12 +297,6 @@ impl Node {
     pub fn connect(a: usize, b: usize, compiler: &mut AtomTreeCompiler) {
         compiler.nodes[a].connections.push(b.clone());
         compiler.nodes[b].connections.push(a.clone());
-        if  compiler.nodes[a].label == Label::Null {
-            return;
-        }
-        if compiler.nodes[a].label == compiler.nodes[b].label {
-            println!("Compilation error lead to not-solved graph")
-        }
     }
 }
 #[derive(Debug, Default, PartialEq, Hash, Eq, Clone, Copy, EnumAsInner)]
@@ -1,71 +1,299 @@
 use std::{collections::HashMap, fmt::Pointer};
 
-use crate::compiler::{atom_tree::{AtomRoot, AtomTree}, atom_tree_to_graph::Label, compilation::Compilation, syntax::{CodeSyntax, CollectionSyntax, CompositeTypeSyntax, ExpressionSyntax, SubCallSyntax, SubLocation, SubstructureSyntax, TypedIdentifierSyntax}, token::{AtomSub, AtomType}};
+use crate::compiler::{atom_tree::{AtomRoot, AtomTree}, atom_tree_to_graph::Label, compilation::Compilation, syntax::{CodeSyntax, CollectionSyntax, CompositeTypeSyntax, EnumEncoding, EnumSyntax, EqualityOperator, ExpressionSyntax, FieldAssignSyntax, IteratorSyntax, MatchArmSyntax, PatternSyntax, SolutionSyntax, SubCallSyntax, SubLocation, SubstructureSyntax, SuperSyntax, TypeSyntax, TypedIdentifierSyntax, find_method}, token::{AtomSub, AtomType}};
 
-use super::{atom_tree::ValueAction, code_location::LocationValue};
+use super::{atom_tree::{OutputValue, ValueAction}, code_location::{CodeLocation, LocationValue}, scope::Scope};
+
+//Early returns of a sub that is being compiled
+struct ReturnState {
+    //Length of the condition stack when the sub was entered, conditions below it belong to the caller
+    depth: usize,
+    //True if the sub has already returned. None if no return has been compiled yet
+    returned: Option<AtomTree>,
+    value: Option<ValueCollection>,
+}
 
 pub struct AtomTreeTranslator<'a> {
     pub collections: Vec<CollectionSyntax>,
     pub atom_tree: AtomRoot,
     pub compilation: &'a  mut Compilation,
     pub composites: Vec<CompositeTypeSyntax>,
+    pub enums: Vec<EnumSyntax>,
     pub condition_stack: Vec<AtomTree>,
+    pub supers: HashMap<String, SuperSyntax>,
+    //Values of the supers evaluated so far, ValueCollection::Error if evaluating failed. None while a super is being evaluated.
+    super_values: HashMap<String, Option<ValueCollection>>,
+    return_stack: Vec<ReturnState>,
+    //Values of the witnesses given by the solution of the problem being compiled
+    witnesses: Vec<FieldAssignSyntax>,
 }
 
 impl<'a> AtomTreeTranslator<'a> {
     pub fn find_composite(&self, name: &String) -> Option<&CompositeTypeSyntax> {
         self.composites.iter().find(|composite| &composite.name.value == name)
     }
-    pub fn new(comp: &'a mut Compilation, collections: Vec<CollectionSyntax>, composites: Vec<CompositeTypeSyntax>) -> Self {
+    pub fn find_enum(&self, name: &String) -> Option<&EnumSyntax> {
+        self.enums.iter().find(|enum_syntax| &enum_syntax.name.value == name)
+    }
+    pub fn new(comp: &'a mut Compilation, collections: Vec<CollectionSyntax>, composites: Vec<CompositeTypeSyntax>, enums: Vec<EnumSyntax>, supers: HashMap<String, SuperSyntax>) -> Self {
         Self {
             compilation: comp,
             collections,
             composites,
+            enums,
+            supers,
+            super_values: HashMap::new(),
+            return_stack: vec![],
+            witnesses: vec![],
             atom_tree: AtomRoot::default(),
             condition_stack: vec![]
         }
     }
-    pub fn convert(mut self, problems: Vec<SubstructureSyntax>, solutions: HashMap<String, SubCallSyntax>) -> AtomRoot {
+    ///Compiles the problems with the inputs of the solution with the given index. Problems with fewer solutions keep their inputs unlabelled
+    pub fn convert(mut self, problems: Vec<SubstructureSyntax>, solutions: &[SolutionSyntax], index: usize) -> AtomRoot {
+        let mut super_names: Vec<String> = self.supers.keys().cloned().collect();
+        super_names.sort();
+        for name in super_names {
+            self.evaluate_super(&name);
+        }
 
         for problem in problems {
-            let mut input = vec![];
-            let solution = match solutions.get(&problem.name.value) {
-                Some(s) if s.application.is_some() => {
-                    let application = s.application.as_ref().unwrap();
-                    match application {
-                        ExpressionSyntax::Literal(l) => {
-                            vec![l.value.to_owned().into()]
+            let solution = solutions.iter().filter(|s| s.problem.value == problem.name.value).nth(index);
+            self.witnesses = solution.map_or(vec![], |s| s.witnesses.clone());
+            let solution: Vec<&ExpressionSyntax> = match solution {
+                Some(solution) => solution.arguments.iter().collect(),
+                None => vec![]
+            };
+            let input = problem.args.iter().enumerate().map(|(i, arg)| self.problem_input(arg, solution.get(i).copied())).collect();
+            self.compile_substructure(&problem, input, vec![], problem.name.location.clone());
+        }
+
+        self.atom_tree
+    }
+
+    ///Creates the seeds of a problem argument, labeled with the value given in the solution
+    fn problem_input(&mut self, arg: &TypedIdentifierSyntax, solution: Option<&ExpressionSyntax>) -> ValueCollection {
+        //Solutions are evaluated like supers, so they can use constructors and subs as long as the result is known at compile time
+        let value = solution.and_then(|solution| self.compile_expression(solution, &mut Scope::new()));
+        let location = solution.map_or(arg.name.location.clone(), |solution| solution.code_location());
+        self.seed_input(&arg.type_syntax, value.as_ref(), Some(&mut Scope::new()), &location)
+    }
+
+    ///Creates one seed for every boolean of the type. The generics are None if the sizes of the type can only be taken from the solution
+    fn seed_input(&mut self, type_syntax: &TypeSyntax, solution: Option<&ValueCollection>, generics: Option<&mut Scope<ValueCollection>>, location: &Option<CodeLocation>) -> ValueCollection {
+        match type_syntax {
+            TypeSyntax::Atom(_) => self.seed_bool(solution, location),
+            TypeSyntax::Sub => {
+                self.compilation.add_error("Problem inputs can't be subs", location.clone());
+                ValueCollection::Error
+            }
+            TypeSyntax::Super => match solution {
+                Some(value @ ValueCollection::Super(_)) => value.clone(),
+                _ => {
+                    self.compilation.add_error("Compile time inputs of a problem need a compile time value in the solution", location.clone());
+                    ValueCollection::Error
+                }
+            },
+            TypeSyntax::Set { elements } => {
+                let items = match solution {
+                    Some(ValueCollection::Tuple(items)) if items.len() == elements.len() => items.iter().map(Some).collect(),
+                    Some(_) => {
+                        self.compilation.add_error(&format!("Expected a tuple of {} elements", elements.len()), location.clone());
+                        vec![None; elements.len()]
+                    }
+                    None => vec![None; elements.len()]
+                };
+                let mut generics = generics;
+                let values = elements.iter().zip(items).map(|(t, item)| self.seed_input(t, item, generics.as_deref_mut(), location)).collect();
+                ValueCollection::Tuple(values)
+            }
+            TypeSyntax::Array { t, size } => {
+                let mut generics = generics;
+                let length = match (size, generics.as_deref_mut()) {
+                    (Some(size), Some(generics)) => self.compile_expression(size, generics).and_then(|size| size.get_as_int_or_error(self.compilation)),
+                    _ => None
+                };
+                let items: Vec<Option<ValueCollection>> = match (solution, length) {
+                    (Some(ValueCollection::Array { items }), _) => items.iter().cloned().map(Some).collect(),
+                    //Integers fill arrays of booleans, least significant bit first
+                    (Some(ValueCollection::Super(SuperValue::Int(int))), Some(length)) => {
+                        if length < usize::BITS as usize && int >> length != 0 {
+                            self.compilation.add_error(&format!("{int} doesn't fit into {length} bits"), location.clone());
                         }
-                        ExpressionSyntax::Tuple(t) => {
-                            t.iter().map(|syntax| {
-                                if let ExpressionSyntax::Literal(l) = syntax {
-                                    l.value.to_owned().into()
-                                } else {
-                                    self.compilation.add_error("Expected tuple of literals", problem.name.location.clone());
-                                    Label::Null.into()
-                                }
-                            }).collect()
+                        (0..length).map(|i| Some(ValueCollection::Super(SuperValue::Bool(i < usize::BITS as usize && int >> i & 1 == 1)))).collect()
+                    }
+                    (Some(ValueCollection::Super(SuperValue::Int(_))), None) => {
+                        self.compilation.add_error("The length of an array input can't be inferred from an integer", location.clone());
+                        vec![]
+                    }
+                    (Some(_), _) => {
+                        self.compilation.add_error("Expected an array", location.clone());
+                        vec![None; length.unwrap_or_default()]
+                    }
+                    (None, Some(length)) => vec![None; length],
+                    (None, None) => {
+                        self.compilation.add_error("The length of an array input can't be inferred without a solution", location.clone());
+                        vec![]
+                    }
+                };
+                let items = items.iter().map(|item| self.seed_input(t, item.as_ref(), generics.as_deref_mut(), location)).collect();
+                ValueCollection::Array { items }
+            }
+            TypeSyntax::Composite { name, .. } if name.value == "bool" => self.seed_bool(solution, location),
+            TypeSyntax::Composite { name, generics: generic_arguments } => {
+                if let Some(enum_syntax) = self.find_enum(&name.value).cloned() {
+                    let labels = match solution {
+                        Some(ValueCollection::Enum { enum_name, bits }) if enum_name == &name.value => bits.iter().map(|bit| self.solution_label(Some(bit), location)).collect(),
+                        Some(_) => {
+                            self.compilation.add_error(&format!("Expected a variant of {}", name.value), location.clone());
+                            vec![Label::Null; enum_syntax.bit_count()]
                         }
-                        _ => {
-                            self.compilation.add_warning("Internal error compiling problem with solution", None);
-                            vec![Label::Null; problem.args.len()]
+                        None => vec![Label::Null; enum_syntax.bit_count()]
+                    };
+                    let bits = labels.into_iter().map(|label| ValueCollection::SingleVar(self.atom_tree.define_new_var(AtomTree::SeedLabel(label)))).collect();
+                    let value = ValueCollection::Enum { enum_name: name.value.clone(), bits };
+                    self.force_valid_enum(&enum_syntax, &value);
+                    return value;
+                }
+                let Some(composite) = self.find_composite(&name.value).cloned() else {
+                    self.compilation.add_error(&format!("Couldn't find type {}", name.value), name.location.clone());
+                    return ValueCollection::Error;
+                };
+                //Without all generic arguments, the sizes of the fields are taken from the solution
+                let mut field_generics = match generics {
+                    Some(generics) if generic_arguments.len() == composite.generics.len() => {
+                        let mut field_generics = Scope::new();
+                        for (generic, argument) in composite.generics.iter().zip(generic_arguments) {
+                            let argument = self.compile_expression(argument, generics).unwrap_or_default();
+                            field_generics.insert(generic.value.clone(), argument);
                         }
+                        Some(field_generics)
+                    }
+                    _ => None
+                };
+                let fields = match solution {
+                    Some(ValueCollection::Composite { composite_name, fields }) if composite_name == &name.value => Some(fields.clone()),
+                    //An integer is taken as the value of the only field of a composite, like the data of an Int
+                    Some(int @ ValueCollection::Super(SuperValue::Int(_))) if composite.fields.len() == 1 => Some(HashMap::from([(composite.fields[0].name.value.clone(), int.clone())])),
+                    Some(_) => {
+                        self.compilation.add_error(&format!("Expected a value of {}", name.value), location.clone());
+                        None
+                    }
+                    None => None
+                };
+                let mut values = HashMap::new();
+                for field in &composite.fields {
+                    let value = fields.as_ref().and_then(|fields| fields.get(&field.name.value));
+                    //Optional fields only exist if the solution sets them
+                    if value.is_none() && field.optional && (fields.is_some() || field.default.is_none()) {
+                        continue;
                     }
+                    let value = self.seed_input(&field.type_syntax, value, field_generics.as_mut(), location);
+                    values.insert(field.name.value.clone(), value);
                 }
-                
-                _ => vec![Label::Null; problem.args.len()],
-            };
-            for (arg, sln) in problem.args.iter().zip(solution.iter()) {
-                let in_var = self.atom_tree.define_new_var(AtomTree::SeedLabel(*sln));
-                input.push(ValueCollection::SingleVar(in_var));
+                ValueCollection::Composite { composite_name: name.value.clone(), fields: values }
             }
-            self.compile_substructure(&problem, input);
         }
+    }
 
-        self.atom_tree
+    fn seed_bool(&mut self, solution: Option<&ValueCollection>, location: &Option<CodeLocation>) -> ValueCollection {
+        let label = self.solution_label(solution, location);
+        ValueCollection::SingleVar(self.atom_tree.define_new_var(AtomTree::SeedLabel(label)))
+    }
+
+    //The label of a boolean given in the solution
+    fn solution_label(&mut self, solution: Option<&ValueCollection>, location: &Option<CodeLocation>) -> Label {
+        match solution {
+            Some(ValueCollection::Single(AtomTree::AtomType { atom })) => (*atom).into(),
+            Some(ValueCollection::Super(SuperValue::Bool(b))) => if *b { Label::True } else { Label::False },
+            Some(_) => {
+                self.compilation.add_error("Expected a boolean that is known at compile time", location.clone());
+                Label::Null
+            }
+            None => Label::Null
+        }
+    }
+
+    ///Forces the bits of an enum value to encode one of its variants, only needed for values that don't come from a variant
+    fn force_valid_enum(&mut self, enum_syntax: &EnumSyntax, value: &ValueCollection) {
+        let ValueCollection::Enum { bits, .. } = value else { return };
+        let bits: Vec<AtomTree> = bits.iter().filter_map(|bit| bit.as_atom_tree()).collect();
+        let mut constraints = vec![];
+        match enum_syntax.encoding {
+            EnumEncoding::OneHot => {
+                //At least one bit is set, and no two bits are set at once
+                constraints.push(Self::any(bits.clone()));
+                for i in 0..bits.len() {
+                    for j in i + 1..bits.len() {
+                        constraints.push(AtomTree::Or(vec![AtomTree::Not(bits[i].clone().into()), AtomTree::Not(bits[j].clone().into())]));
+                    }
+                }
+            }
+            EnumEncoding::Binary => {
+                //Codes past the last variant are invalid
+                for code in enum_syntax.variants.len()..1 << bits.len() {
+                    let code: Vec<bool> = (0..bits.len()).map(|bit| code >> bit & 1 == 1).collect();
+                    constraints.push(AtomTree::Not(Self::code_condition(&bits, &code).into()));
+                }
+            }
+        }
+        for constraint in constraints {
+            self.force(ValueCollection::Single(constraint), AtomType::True);
+        }
     }
 
-    pub fn map_args(&mut self, mut input_args: Vec<ValueCollection>, map_args: &Vec<TypedIdentifierSyntax>, map: &mut HashMap<String, ValueCollection>) {
+    //True if the bits are equal to the code
+    fn code_condition(bits: &[AtomTree], code: &[bool]) -> AtomTree {
+        let mismatches = bits.iter().zip(code).map(|(bit, set)| if *set { AtomTree::Not(bit.clone().into()) } else { bit.clone() }).collect();
+        AtomTree::Not(Self::any(mismatches).into())
+    }
+
+    //True if the enum value is the variant at the index
+    fn variant_condition(enum_syntax: &EnumSyntax, bits: &[ValueCollection], index: usize) -> AtomTree {
+        let bits: Vec<AtomTree> = bits.iter().filter_map(|bit| bit.as_atom_tree()).collect();
+        match enum_syntax.encoding {
+            //Exactly one bit is set, so the bit of the variant is enough
+            EnumEncoding::OneHot => bits[index].clone(),
+            EnumEncoding::Binary => Self::code_condition(&bits, &enum_syntax.encode(index)),
+        }
+    }
+
+    //True if any of the values is true
+    fn any(mut values: Vec<AtomTree>) -> AtomTree {
+        match values.len() {
+            0 => AtomTree::AtomType { atom: AtomType::False },
+            1 => values.remove(0),
+            _ => AtomTree::Or(values)
+        }
+    }
+
+    ///Evaluates a super and the supers it depends on. Every super is only evaluated once.
+    pub fn evaluate_super(&mut self, name: &str) -> Option<SuperValue> {
+        let super_syntax = self.supers.get(name)?.clone();
+        match self.super_values.get(name) {
+            Some(Some(ValueCollection::Super(value))) => return Some(value.clone()),
+            Some(Some(_)) => return None,
+            Some(None) => {
+                self.compilation.add_error(&format!("Super {name} depends on itself"), super_syntax.name.location.clone());
+                return None;
+            }
+            None => {}
+        }
+        self.super_values.insert(name.to_owned(), None);
+        let value = match self.compile_expression(&super_syntax.value, &mut Scope::new()) {
+            Some(ValueCollection::Super(value)) => Some(value),
+            Some(_) => {
+                self.compilation.add_error(&format!("Super {name} must evaluate to a compile time integer or string"), super_syntax.value.code_location());
+                None
+            }
+            None => None
+        };
+        //Failed supers are stored as well, so their errors are only reported once
+        self.super_values.insert(name.to_owned(), Some(value.clone().map(ValueCollection::Super).unwrap_or_default()));
+        value
+    }
+
+    pub fn map_args(&mut self, mut input_args: Vec<ValueCollection>, map_args: &[TypedIdentifierSyntax], map: &mut Scope<ValueCollection>) {
         assert_eq!(map_args.len(), input_args.len());
     
         for i in (0..map_args.len()).rev() {
@@ -76,7 +304,7 @@ impl<'a> AtomTreeTranslator<'a> {
 
     ///Mutate value such that it is always valid if forced if any condition is not met
     pub fn mutate_value_to_enforce_at_condition(&self, mut value: AtomTree, t: AtomType) -> AtomTree {
-        for condition in &self.condition_stack {
+        for condition in &self.active_conditions() {
             match t {
                 AtomType::True => {
                     value = AtomTree::Or(vec![value, AtomTree::Not(condition.to_owned().into())])
@@ -107,20 +335,177 @@ impl<'a> AtomTreeTranslator<'a> {
                     self.force(value, t);
                 }
             }
+            ValueCollection::Super(SuperValue::Bool(b)) => {
+                self.force(ValueCollection::Single(AtomTree::AtomType { atom: if b { AtomType::True } else { AtomType::False } }), t);
+            }
             _ => {
                 self.compilation.add_error("Can only force tuple types or single types", None);
             }
         }
     }
 
-    pub fn compile_substructure(&mut self, substructure: &SubstructureSyntax, inputs: Vec<ValueCollection>) -> Option<ValueCollection> {
-        let mut variables = HashMap::new();
+    ///Compiles a sub for the given arguments. Generics that aren't given explicitly are inferred from the arguments, so every call gets its own copy of the sub.
+    pub fn compile_substructure(&mut self, substructure: &SubstructureSyntax, inputs: Vec<ValueCollection>, generics: Vec<usize>, call_location: Option<CodeLocation>) -> Option<ValueCollection> {
+        let mut variables = Scope::new();
         self.map_args(inputs, &substructure.args, &mut variables);
+        for (generic, value) in substructure.generics.iter().zip(generics) {
+            variables.insert(generic.value.clone(), ValueCollection::Super(SuperValue::Int(value)));
+        }
+        //Array sizes may refer to earlier arguments, so they are checked after all arguments are mapped
+        let mut mismatched = false;
+        for arg in &substructure.args {
+            let value = variables.get(&arg.name.value).cloned().unwrap_or_default();
+            if let Err(e) = self.match_type(&value, &arg.type_syntax, &substructure.generics, &mut variables) {
+                self.compilation.add_error(&format!("Argument {} of {} expects {e}", arg.name.value, substructure.name.value), call_location.clone());
+                mismatched = true;
+            }
+        }
+        //Compiling the sub with mismatched arguments would only lead to follow up errors
+        if mismatched {
+            return None;
+        }
+        for generic in &substructure.generics {
+            if variables.get(&generic.value).is_none() && !substructure.is_type_generic(&generic.value) {
+                self.compilation.add_error(&format!("Couldn't infer generic {} of {}, it has to be given explicitly, like {}<...>", generic.value, substructure.name.value, substructure.name.value), call_location.clone());
+                return None;
+            }
+        }
+        self.return_stack.push(ReturnState { depth: self.condition_stack.len(), returned: None, value: None });
         self.compile_code_block(&substructure.code, &mut variables);
-        if let Some(res) = &substructure.result {
-            self.compile_expression(res, &mut variables)
-        } else {
-            Some(ValueCollection::Tuple(vec![]))
+        let always_returned = self.return_stack.last().and_then(|state| state.returned.as_ref()).is_some_and(|returned| returned.as_atom_type() == Some(&AtomType::True));
+        //The result is still compiled while the return state is active, so its forces are skipped if the sub returned
+        let result = match &substructure.result {
+            Some(res) if !always_returned => self.compile_expression(res, &mut variables),
+            _ => Some(ValueCollection::Tuple(vec![]))
+        };
+        let state = self.return_stack.pop().expect("Expected return stack to be non-empty");
+        match (state.returned, state.value) {
+            (Some(_), Some(value)) if always_returned || substructure.result.is_none() => Some(value),
+            (Some(returned), Some(value)) => self.select_values_if_conditions_met(value, result?, &returned, substructure.name.location.clone()),
+            _ => result
+        }
+    }
+
+    ///Checks the array lengths and generic arguments in a value against its type. Generics that aren't in the scope yet are bound to the values found.
+    ///Returns the expected and found value on a mismatch.
+    fn match_type(&mut self, value: &ValueCollection, type_syntax: &TypeSyntax, generics: &[LocationValue<String>], variables: &mut Scope<ValueCollection>) -> Result<(), String> {
+        match (type_syntax, value) {
+            (TypeSyntax::Array { t, size }, ValueCollection::Array { items }) => {
+                if let Some(size) = size {
+                    self.match_size(size, items.len(), generics, variables).map_err(|expected| format!("an array of length {expected}, found {}", items.len()))?;
+                }
+                items.iter().try_for_each(|item| self.match_type(item, t, generics, variables))
+            }
+            (TypeSyntax::Set { elements }, ValueCollection::Tuple(items)) if elements.len() == items.len() => {
+                elements.iter().zip(items).try_for_each(|(t, item)| self.match_type(item, t, generics, variables))
+            }
+            (TypeSyntax::Composite { name, generics: arguments }, ValueCollection::Composite { composite_name, fields }) if !arguments.is_empty() && &name.value == composite_name => {
+                let (parameters, values) = self.composite_generics(composite_name, fields)?;
+                for ((argument, parameter), value) in arguments.iter().zip(parameters).zip(values) {
+                    self.match_size(argument, value, generics, variables).map_err(|expected| format!("{composite_name} with {} = {expected}, found {value}", parameter.value))?;
+                }
+                Ok(())
+            }
+            _ => Ok(())
+        }
+    }
+
+    //Binds an unbound generic to the found size, otherwise checks the size. Returns the expected size on a mismatch.
+    fn match_size(&mut self, size: &ExpressionSyntax, found: usize, generics: &[LocationValue<String>], variables: &mut Scope<ValueCollection>) -> Result<(), usize> {
+        if let ExpressionSyntax::Variable(name) = size {
+            if generics.iter().any(|generic| generic.value == name.value) && variables.get(&name.value).is_none() {
+                variables.insert(name.value.clone(), ValueCollection::Super(SuperValue::Int(found)));
+                return Ok(());
+            }
+        }
+        match self.compile_expression(size, variables).and_then(|size| size.get_as_int_or_error(self.compilation)) {
+            Some(expected) if expected != found => Err(expected),
+            _ => Ok(())
+        }
+    }
+
+    ///Infers the generic arguments of a composite value from its fields
+    fn composite_generics(&mut self, composite_name: &String, fields: &HashMap<String, ValueCollection>) -> Result<(Vec<LocationValue<String>>, Vec<usize>), String> {
+        let composite = match self.find_composite(composite_name) {
+            Some(composite) => composite.clone(),
+            None => return Ok((vec![], vec![]))
+        };
+        let mut scope = Scope::new();
+        for field in &composite.fields {
+            if let Some(value) = fields.get(&field.name.value) {
+                self.match_type(value, &field.type_syntax, &composite.generics, &mut scope)?;
+            }
+        }
+        let values = composite.generics.iter().map(|generic| match scope.get(&generic.value) {
+            Some(ValueCollection::Super(SuperValue::Int(value))) => Ok(*value),
+            _ => Err(format!("a value of {composite_name} where {} is known", generic.value))
+        }).collect::<Result<_, _>>()?;
+        Ok((composite.generics, values))
+    }
+
+    ///Compiles an early return. The returned value replaces the values of earlier returns if the sub hasn't returned yet.
+    fn compile_return(&mut self, value: ValueCollection, location: Option<CodeLocation>) -> Option<()> {
+        let mut state = self.return_stack.pop()?;
+        let condition = Self::true_if_all_met(&self.condition_stack[state.depth..]);
+        let (returned, value) = match (state.returned.take(), state.value.take()) {
+            (Some(returned), Some(old_value)) => {
+                //Only the first return that is reached decides the value
+                let fired = AtomTree::Not(AtomTree::Or(vec![AtomTree::Not(condition.clone().into()), returned.clone()]).into());
+                let fired = AtomTree::Variable { id: self.atom_tree.define_new_var(fired) };
+                let value = self.select_values_if_conditions_met(value, old_value, &fired, location);
+                let returned = match condition.as_atom_type() {
+                    Some(AtomType::True) => condition,
+                    _ => AtomTree::Or(vec![returned, condition])
+                };
+                (returned, value)
+            }
+            _ => (condition, Some(value)),
+        };
+        state.returned = Some(match returned.as_atom_type() {
+            Some(_) => returned,
+            None => AtomTree::Variable { id: self.atom_tree.define_new_var(returned) }
+        });
+        state.value = value.map(|value| value.write_as_var(self));
+        self.return_stack.push(state);
+        Some(())
+    }
+
+    ///Selects between two values of the same shape. Compile time values have to be equal.
+    pub fn select_values_if_conditions_met(&mut self, select_if_true: ValueCollection, select_if_false: ValueCollection, condition: &AtomTree, location: Option<CodeLocation>) -> Option<ValueCollection> {
+        match (select_if_true, select_if_false) {
+            (ValueCollection::Error, _) | (_, ValueCollection::Error) => Some(ValueCollection::Error),
+            (ValueCollection::Tuple(a), ValueCollection::Tuple(b)) if a.len() == b.len() => {
+                let values = a.into_iter().zip(b).map(|(a, b)| self.select_values_if_conditions_met(a, b, condition, location.clone())).collect::<Option<_>>()?;
+                Some(ValueCollection::Tuple(values))
+            }
+            (ValueCollection::Array { items: a }, ValueCollection::Array { items: b }) if a.len() == b.len() => {
+                let items = a.into_iter().zip(b).map(|(a, b)| self.select_values_if_conditions_met(a, b, condition, location.clone())).collect::<Option<_>>()?;
+                Some(ValueCollection::Array { items })
+            }
+            (ValueCollection::Composite { composite_name, fields: a }, ValueCollection::Composite { composite_name: b_name, fields: mut b }) 
+                if composite_name == b_name && a.len() == b.len() && a.keys().all(|k| b.contains_key(k)) => {
+                let mut fields = HashMap::new();
+                for (name, a) in a {
+                    let b = b.remove(&name)?;
+                    fields.insert(name, self.select_values_if_conditions_met(a, b, condition, location.clone())?);
+                }
+                Some(ValueCollection::Composite { composite_name, fields })
+            }
+            (ValueCollection::Enum { enum_name, bits: a }, ValueCollection::Enum { enum_name: b_name, bits: b }) if enum_name == b_name && a.len() == b.len() => {
+                let bits = a.into_iter().zip(b).map(|(a, b)| self.select_values_if_conditions_met(a, b, condition, location.clone())).collect::<Option<_>>()?;
+                Some(ValueCollection::Enum { enum_name, bits })
+            }
+            (ValueCollection::Super(a), ValueCollection::Super(b)) if a == b => Some(ValueCollection::Super(a)),
+            (ValueCollection::SubReference { collection, sub }, ValueCollection::SubReference { collection: b_collection, sub: b_sub }) if collection == b_collection && sub == b_sub => {
+                Some(ValueCollection::SubReference { collection, sub })
+            }
+            (a, b) => match (a.as_atom_tree(), b.as_atom_tree()) {
+                (Some(a), Some(b)) => Some(ValueCollection::Single(Self::select_if_conditions_met(a, b, condition.clone()))),
+                _ => {
+                    self.compilation.add_error("Can't select between values of different shapes or compile time values at runtime", location);
+                    None
+                }
+            }
         }
     }
     pub fn select_if_conditions_met(select_if_true: AtomTree, select_if_false: AtomTree, condition: AtomTree) -> AtomTree {
@@ -146,63 +531,192 @@ impl<'a> AtomTreeTranslator<'a> {
         selected
     }
 
-    pub fn compile_code_block(&mut self, block: &Vec<CodeSyntax>, variables: &mut HashMap<String, ValueCollection>) -> Option<()> {
-        let compilation = unsafe {self.extract_compilation()};
+    fn reassign(&mut self, variable: &ExpressionSyntax, new_value: ValueCollection, location: Option<CodeLocation>, variables: &mut Scope<ValueCollection>) -> Option<()> {
+        let condition = self.true_if_all_conditions_are_met();
+
+        let var = self.compile_access_expression(variable, variables)?;
+
+        if let Some(AtomType::True) = condition.as_atom_type() {
+            *var = new_value;
+        } else {
+            //The condition is shared by every leaf of the selected value
+            let condition = AtomTree::Variable { id: self.atom_tree.define_new_var(condition) };
+            let selected = self.select_values_if_conditions_met(new_value, var.clone(), &condition, location)?;
+            *var = selected.write_as_var(self);
+        }
+        Some(())
+    }
+
+    //Binds or reassigns every part of the pattern to the matching part of the value
+    fn destructure(&mut self, pattern: &PatternSyntax, value: ValueCollection, is_let: bool, variables: &mut Scope<ValueCollection>) -> Option<()> {
+        match (pattern, value) {
+            (PatternSyntax::Single(ExpressionSyntax::Variable(name)), value) if is_let => {
+                variables.insert(name.value.to_owned(), value.write_as_var(self));
+            }
+            (PatternSyntax::Single(variable), value) => {
+                self.reassign(variable, value, variable.code_location(), variables)?;
+            }
+            //Values that failed to compile were already reported
+            (PatternSyntax::Tuple { elements, .. }, ValueCollection::Error) => {
+                for element in elements {
+                    self.destructure(element, ValueCollection::Error, is_let, variables);
+                }
+            }
+            (PatternSyntax::Composite { fields, .. }, ValueCollection::Error) => {
+                for (_, field_pattern) in fields {
+                    self.destructure(field_pattern, ValueCollection::Error, is_let, variables);
+                }
+            }
+            (PatternSyntax::Tuple { elements, .. }, ValueCollection::Tuple(items)) if elements.len() == items.len() => {
+                for (element, item) in elements.iter().zip(items) {
+                    self.destructure(element, item, is_let, variables);
+                }
+            }
+            (PatternSyntax::Composite { type_name, fields, .. }, ValueCollection::Composite { composite_name, fields: mut values }) if type_name.value == composite_name => {
+                for (field, field_pattern) in fields {
+                    match values.remove(&field.value) {
+                        Some(value) => {
+                            self.destructure(field_pattern, value, is_let, variables);
+                        }
+                        None => {
+                            self.compilation.add_error(&format!("Field \"{}\" not found in composite type. Optional fields can only be destructured if they were assigned", field.value), field.location.clone());
+                        }
+                    }
+                }
+            }
+            (pattern, _) => {
+                self.compilation.add_error("Value doesn't match the shape of the pattern", pattern.code_location());
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    //Loops are unrolled, the body is compiled once for every value of the iterator
+    fn compile_for(&mut self, iterator_variable: &LocationValue<String>, index_variable: Option<&LocationValue<String>>, iterator: &IteratorSyntax, body: &[CodeSyntax], variables: &mut Scope<ValueCollection>) -> Option<()> {
+        let (values, array) = match iterator {
+            IteratorSyntax::Value(value) => match self.compile_expression(value, variables)? {
+                ValueCollection::Super(SuperValue::Int(amount)) => ((0..amount).collect::<Vec<_>>(), None),
+                ValueCollection::Array { items } => ((0..items.len()).collect(), Some((value, items))),
+                _ => {
+                    self.compilation.add_error("Can only iterate over super integers and arrays", value.code_location());
+                    return None;
+                }
+            }
+            IteratorSyntax::Range { start, end, step } => {
+                let start = self.compile_expression(start, variables)?.get_as_int_or_error(self.compilation)?;
+                let end = self.compile_expression(end, variables)?.get_as_int_or_error(self.compilation)?;
+                let step = match step {
+                    Some(step) => self.compile_expression(step, variables)?.get_as_int_or_error(self.compilation)?,
+                    None => 1
+                };
+                if step == 0 {
+                    self.compilation.add_error("The step of a range can't be 0", iterator_variable.location.clone());
+                    return None;
+                }
+                ((start..end).step_by(step).collect(), None)
+            }
+        };
+        //Elements of arrays that are variables are read every iteration, since the body might change them, and written back if the loop variable is reassigned
+        let array_place = array.as_ref().map(|(value, _)| *value).filter(|value| value.is_place());
+        let write_back = array_place.is_some() && writes_to(body, &iterator_variable.value);
+
+        for (position, value) in values.into_iter().enumerate() {
+            let element_syntax = array_place.map(|array| ExpressionSyntax::IndexOp {
+                base: array.clone().into(),
+                index: ExpressionSyntax::Int(LocationValue::new(iterator_variable.location.clone(), value)).into()
+            });
+            let element = match (&element_syntax, &array) {
+                (Some(element_syntax), _) => self.compile_expression(element_syntax, variables)?,
+                (None, Some((_, items))) => items[value].clone(),
+                (None, None) => ValueCollection::Super(SuperValue::Int(value))
+            };
+            variables.push();
+            if let Some(index_variable) = index_variable {
+                variables.insert(index_variable.value.to_owned(), ValueCollection::Super(SuperValue::Int(position)));
+            }
+            variables.insert(iterator_variable.value.to_owned(), element);
+            self.compile_code_block(body, variables);
+            let written = if write_back { variables.get(&iterator_variable.value).cloned() } else { None };
+            variables.pop();
+            if let (Some(element_syntax), Some(written)) = (element_syntax, written) {
+                self.reassign(&element_syntax, written, iterator_variable.location.clone(), variables);
+            }
+        }
+        Some(())
+    }
+
+    pub fn compile_code_block(&mut self, block: &[CodeSyntax], variables: &mut Scope<ValueCollection>) -> Option<()> {
         for statement in block {
             match statement {
-                CodeSyntax::For { iterator_variable, iterator_amount, iterator_body } => {
-                    let iterator_amount = self.compile_expression(iterator_amount, variables)?.get_as_int_or_error(self.compilation)?;
-                    for i in 0..iterator_amount {
-                        variables.insert(iterator_variable.value.to_owned(), ValueCollection::Super(SuperValue::Int(i)));
-                        self.compile_code_block(iterator_body, variables);
-                    }
+                CodeSyntax::For { iterator_variable, index_variable, iterator, iterator_body } => {
+                    self.compile_for(iterator_variable, index_variable.as_ref(), iterator, iterator_body, variables)?;
                 }
                 CodeSyntax::ReassignSyntax { variable, value } => {
-                    let condition = self.true_if_all_conditions_are_met();
-
                     let new_value = self.compile_expression(value, variables)?;
-
-                    let var = self.compile_access_expression(variable, variables)?;
-                    
-                    //Hack to allow writing non-atom-type values to variables
-                    if let Some(AtomType::True) = condition.as_atom_type() {
-                        *var = new_value;
-                    } else {
-                        
-                        let old_value = var.clone().get_as_atom_tree_if_single_or_error(compilation)?;
-    
-                        let new_value = new_value.get_as_atom_tree_if_single_or_error(self.compilation)?;
-    
-                        let selected = Self::select_if_conditions_met(new_value, old_value, condition);
-                        //println!("Selected: {:#?}", selected);
-                        *var = ValueCollection::Single(selected);
-                    }
-                    
+                    self.reassign(variable, new_value, value.code_location(), variables);
 
                     //println!("Vars: {:#?}", variables);
                 }
+                CodeSyntax::LetPattern { pattern, value } => {
+                    let value = self.compile_expression(value, variables).unwrap_or_default();
+                    self.destructure(pattern, value, true, variables);
+                }
+                CodeSyntax::ReassignPattern { pattern, value } => {
+                    let value = self.compile_expression(value, variables)?;
+                    self.destructure(pattern, value, false, variables);
+                }
                 //A conditional code block only changes force statements to always be valid iff the condition is not met
                 CodeSyntax::If { condition, condition_true } => {
-                    let condition = self.compile_expression(condition, variables)?.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
+                    let condition = self.compile_expression(condition, variables)?;
+                    //Conditions known at compile time only compile the taken branch
+                    if let Some(condition) = condition.get_as_super_bool() {
+                        if condition {
+                            variables.push();
+                            self.compile_code_block(condition_true, variables);
+                            variables.pop();
+                        }
+                        continue;
+                    }
+                    let condition = condition.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
                     self.condition_stack.push(condition);
+                    variables.push();
                     self.compile_code_block(condition_true, variables);
+                    variables.pop();
                     self.condition_stack.pop();
                 }
                 CodeSyntax::IfElse { condition, condition_true, condition_false } => {
-                    let condition = self.compile_expression(condition, variables)?.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
+                    let condition = self.compile_expression(condition, variables)?;
+                    if let Some(condition) = condition.get_as_super_bool() {
+                        variables.push();
+                        self.compile_code_block(if condition { condition_true } else { condition_false }, variables);
+                        variables.pop();
+                        continue;
+                    }
+                    let condition = condition.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
                     self.condition_stack.push(condition);
+                    variables.push();
                     self.compile_code_block(condition_true, variables);
+                    variables.pop();
                     let condition = self.condition_stack.pop().expect("Expected condition stack to be non-empty");
 
                     let inverted_condition = AtomTree::Not(condition.into());
                     self.condition_stack.push(inverted_condition);
+                    variables.push();
                     self.compile_code_block(condition_false, variables);
+                    variables.pop();
                     self.condition_stack.pop();
                 }
                 CodeSyntax::Let { variable, value } => {
                     let value = self.compile_expression(&value, variables).unwrap_or_default();    
                     variables.insert(variable.value.to_owned(), value.write_as_var(self));
                 }
+                CodeSyntax::Witness { declaration } => {
+                    //Witnesses are seeded like problem inputs, and stay unlabelled if the solution doesn't give them
+                    let solution = self.witnesses.iter().find(|w| w.left.value == declaration.name.value).map(|w| w.right.clone());
+                    let value = self.problem_input(declaration, solution.as_ref());
+                    variables.insert(declaration.name.value.to_owned(), value);
+                }
                 CodeSyntax::Force { value, type_syntax } => {
                     let value = self.compile_expression(&value, variables)?;
                     let force_type = *type_syntax.as_atom().expect("Todo: Force other types");
@@ -221,43 +735,150 @@ impl<'a> AtomTreeTranslator<'a> {
                     let mut cur_str = String::new();
                     self.format(&mut string_buffer, &mut value_buffer, value, &mut cur_str);
                     string_buffer.push(cur_str);
-                    let condition = self.true_if_all_conditions_are_met();
+                    let condition = Self::true_if_all_met(&self.active_conditions());
                     self.atom_tree.value_actions.push((condition, ValueAction::Output(string_buffer, value_buffer)));
                 }
-                _ => {todo!()}
+                CodeSyntax::StaticAssert { condition, message, location } => {
+                    let condition_value = self.compile_expression(condition, variables)?;
+                    let condition_value = match condition_value.get_as_super_bool() {
+                        Some(b) => b,
+                        None => {
+                            self.compilation.add_error("Expected a compile time condition", condition.code_location());
+                            continue;
+                        }
+                    };
+                    if !condition_value {
+                        let message = match message {
+                            Some(message) => self.compile_super_message(message, variables)?,
+                            None => "Static assertion failed".to_string()
+                        };
+                        self.compilation.add_error(&message, location.clone());
+                    }
+                }
+                CodeSyntax::CompileError { message, location } => {
+                    let message = self.compile_super_message(message, variables)?;
+                    self.compilation.add_error(&message, location.clone());
+                }
+                CodeSyntax::Return { value: value_syntax } => {
+                    let value = self.compile_expression(value_syntax, variables)?;
+                    self.compile_return(value, value_syntax.code_location());
+                }
+                CodeSyntax::Match { value: value_syntax, arms } => {
+                    let value = self.compile_expression(value_syntax, variables)?.write_as_var(self);
+                    let (enum_syntax, bits) = match value {
+                        ValueCollection::Enum { enum_name, bits } => (self.find_enum(&enum_name)?.clone(), bits),
+                        _ => {
+                            self.compilation.add_error("Can only match on enums", value_syntax.code_location());
+                            continue;
+                        }
+                    };
+                    let arm_matches = |arm: &MatchArmSyntax, index: usize| arm.variants.as_ref().is_none_or(|variants| variants.iter().any(|v| enum_syntax.variant_index(&v.value) == Some(index)));
+                    //Values known at compile time only compile the matching arm
+                    if let Some(bits) = bits.iter().map(|bit| bit.get_as_super_bool()).collect::<Option<Vec<_>>>() {
+                        let index = (0..enum_syntax.variants.len()).find(|i| enum_syntax.encode(*i) == bits);
+                        if let Some(arm) = index.and_then(|index| arms.iter().find(|arm| arm_matches(arm, index))) {
+                            variables.push();
+                            self.compile_code_block(&arm.code, variables);
+                            variables.pop();
+                        }
+                        continue;
+                    }
+                    let mut previous_arms = vec![];
+                    for arm in arms {
+                        let condition = match &arm.variants {
+                            Some(variants) => Self::any(variants.iter().filter_map(|v| enum_syntax.variant_index(&v.value)).map(|index| Self::variant_condition(&enum_syntax, &bits, index)).collect()),
+                            None => AtomTree::Not(Self::any(previous_arms.clone()).into())
+                        };
+                        let condition = AtomTree::Variable { id: self.atom_tree.define_new_var(condition) };
+                        previous_arms.push(condition.clone());
+                        self.condition_stack.push(condition);
+                        variables.push();
+                        self.compile_code_block(&arm.code, variables);
+                        variables.pop();
+                        self.condition_stack.pop();
+                    }
+                }
             }
         }
         Some(())
     }
 
+    //Compares two booleans, or two values of the same enum bit by bit. Booleans are equal if both or neither are true
+    fn compile_eq(&mut self, a: ValueCollection, b: ValueCollection) -> Option<ValueCollection> {
+        let (a, b) = match (a, b) {
+            (ValueCollection::Enum { bits: a, .. }, ValueCollection::Enum { bits: b, .. }) => (a, b),
+            (a, b) => (vec![a], vec![b])
+        };
+        if let Some(equal) = a.iter().zip(&b).map(|(a, b)| Some(a.get_as_super_bool()? == b.get_as_super_bool()?)).collect::<Option<Vec<_>>>() {
+            return Some(ValueCollection::Super(SuperValue::Bool(equal.into_iter().all(|equal| equal))));
+        }
+        let mut bits_equal = vec![];
+        for (a, b) in a.into_iter().zip(b) {
+            let a = a.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
+            let b = b.write_as_var(self).get_as_atom_tree_if_single_or_error(self.compilation)?;
+            let both = AtomTree::Not(AtomTree::Or(vec![AtomTree::Not(a.clone().into()), AtomTree::Not(b.clone().into())]).into());
+            let neither = AtomTree::Not(AtomTree::Or(vec![a, b]).into());
+            bits_equal.push(AtomTree::Or(vec![both, neither]));
+        }
+        if bits_equal.len() == 1 {
+            return Some(ValueCollection::Single(bits_equal.remove(0)));
+        }
+        //All bits are equal if none of them differ
+        let bits_differ = bits_equal.into_iter().map(|equal| AtomTree::Not(equal.into())).collect();
+        Some(ValueCollection::Single(AtomTree::Not(Self::any(bits_differ).into())))
+    }
+
+    fn compile_super_message(&mut self, message: &ExpressionSyntax, variables: &mut Scope<ValueCollection>) -> Option<String> {
+        match self.compile_expression(message, variables)? {
+            ValueCollection::Super(value) => Some(value.to_string()),
+            _ => {
+                self.compilation.add_error("Expected a compile time message", message.code_location());
+                None
+            }
+        }
+    }
+
+    //Variables are local to a sub, so only the conditions of the current sub matter when reassigning them
     fn true_if_all_conditions_are_met(&self) -> AtomTree {
+        let depth = self.return_stack.last().map_or(0, |state| state.depth);
+        Self::true_if_all_met(&self.condition_stack[depth..])
+    }
+
+    //Conditions under which forces and outputs take effect. Besides the branch conditions, no sub that is being compiled may have returned yet.
+    fn active_conditions(&self) -> Vec<AtomTree> {
+        let mut conditions = self.condition_stack.clone();
+        conditions.extend(self.return_stack.iter().filter_map(|state| state.returned.clone()).map(|returned| AtomTree::Not(returned.into())));
+        conditions
+    }
+
+    fn true_if_all_met(conditions: &[AtomTree]) -> AtomTree {
         let mut cur = AtomTree::AtomType { atom: AtomType::True };
-        for condition in &self.condition_stack {
+        for condition in conditions {
             cur = AtomTree::Not(AtomTree::Or(vec![AtomTree::Not(condition.to_owned().into()), AtomTree::Not(cur.into())]).into());
         }
         cur
     }
 
-    fn format(&mut self, string_buffer: &mut Vec<String>, value_buffer: &mut Vec<AtomTree>, value: ValueCollection, current_string: &mut String) {
+    fn format(&mut self, string_buffer: &mut Vec<String>, value_buffer: &mut Vec<OutputValue>, value: ValueCollection, current_string: &mut String) {
         match value {
             ValueCollection::Error => {
                 current_string.push_str("<Error>");
             }
-            ValueCollection::Super(SuperValue::Int(i)) => {
-                current_string.push_str(&i.to_string());
+            ValueCollection::Super(value) => {
+                current_string.push_str(&value.to_string());
             }
-            ValueCollection::Super(SuperValue::String(s)) => {
-                current_string.push_str(&s);
+            ValueCollection::SubReference { collection, sub } => {
+                current_string.push_str(&format!("{collection}::{sub}"));
             }
             ValueCollection::Array { items } => {
-                current_string.push_str("[");
+                current_string.push('[');
                 for val in items {
 
                     self.format(string_buffer, value_buffer, val, current_string);
                     current_string.push_str(", ");
 
                 }
-                current_string.push_str("]");
+                current_string.push(']');
             }
             ValueCollection::Composite { composite_name, fields } => {
                 current_string.push_str(&composite_name);
@@ -271,26 +892,51 @@ impl<'a> AtomTreeTranslator<'a> {
                 current_string.push_str(" } ");
 
             }
+            ValueCollection::Enum { enum_name, bits } => {
+                let enum_syntax = self.find_enum(&enum_name);
+                let variants: Vec<_> = enum_syntax.iter().flat_map(|e| e.variants.iter().enumerate().map(|(i, v)| (v.value.clone(), e.encode(i)))).collect();
+                let known: Option<Vec<bool>> = bits.iter().map(|bit| bit.get_as_super_bool()).collect();
+                let trees: Option<Vec<AtomTree>> = bits.iter().map(|bit| bit.as_atom_tree()).collect();
+                //Known bits are printed right away, the others once the graph has been run
+                if let Some(variant) = known.and_then(|known| variants.iter().find(|(_, encoding)| *encoding == known)) {
+                    current_string.push_str(&format!("{enum_name}.{}", variant.0));
+                    return;
+                }
+                if let Some(bits) = trees.filter(|_| enum_syntax.is_some()) {
+                    let mut push_string = String::new();
+                    std::mem::swap(current_string, &mut push_string);
+                    string_buffer.push(push_string);
+                    value_buffer.push(OutputValue::Enum { name: enum_name, variants, bits });
+                    return;
+                }
+                current_string.push_str(&enum_name);
+                current_string.push('(');
+                for bit in bits {
+                    self.format(string_buffer, value_buffer, bit, current_string);
+                    current_string.push_str(", ");
+                }
+                current_string.push(')');
+            }
             ValueCollection::Single(tree) => {
                 let mut push_string = String::new();
                 std::mem::swap(current_string, &mut push_string); 
                 string_buffer.push(push_string);
-                value_buffer.push(tree);
+                value_buffer.push(OutputValue::Label(tree));
             }
             ValueCollection::SingleVar(v) => {
                 let mut push_string = String::new();
                 std::mem::swap(current_string, &mut push_string); 
                 string_buffer.push(push_string);
-                value_buffer.push(AtomTree::Variable { id: v });
+                value_buffer.push(OutputValue::Label(AtomTree::Variable { id: v }));
             }
             ValueCollection::Tuple(t) => {
-                current_string.push_str("(");
+                current_string.push('(');
                 for val in t {
                     self.format(string_buffer, value_buffer, val, current_string);
                     current_string.push_str(", ");
 
                 }
-                current_string.push_str(")");
+                current_string.push(')');
             }
             _ => {
                 self.compilation.add_error("Type cannot be formatted for outputting", None);
@@ -298,7 +944,27 @@ impl<'a> AtomTreeTranslator<'a> {
         }
     }
 
-    pub fn compile_sub_call(&mut self, sub_call_syntax: &SubCallSyntax, variables: &mut HashMap<String, ValueCollection>) -> Option<ValueCollection> {
+    //Cloned because we would need to borrow self as mut later.
+    fn find_sub(&mut self, collection: &LocationValue<String>, sub: &LocationValue<String>) -> Option<SubstructureSyntax> {
+        let collection_ref = self.collections.iter().find(|&f| f.name.value == collection.value);
+        let collection_ref = match collection_ref {
+            None => {
+                self.compilation.add_error(&format!("Couldn't find collection \"{}\"", collection.value), collection.location.to_owned());
+                return None;
+            }
+            Some(collection) => collection
+        };
+        let sub_ref = collection_ref.subs.iter().find(|&f| f.name.value == sub.value);
+        match sub_ref {
+            None => {
+                self.compilation.add_error(&format!("Couldn't find sub in collection: \"{}\"", collection.value), collection.location.to_owned());
+                None
+            }
+            Some(sub) => Some(sub.clone())
+        }
+    }
+
+    pub fn compile_sub_call(&mut self, sub_call_syntax: &SubCallSyntax, variables: &mut Scope<ValueCollection>) -> Option<ValueCollection> {
         //println!("Sub call: {:#?}", sub_call_syntax.application);
         let application = match  &sub_call_syntax.application {
             Some(application) => self.compile_expression(application, variables)?,
@@ -321,6 +987,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 1 {
                             match application.pop()? {
                                 ValueCollection::Single(AtomTree::AtomType { atom }) => Some(ValueCollection::Single(AtomTree::SeedLabel(if atom.is_true() {Label::True} else {Label::False}))),
+                                ValueCollection::Super(SuperValue::Bool(b)) => Some(ValueCollection::Single(AtomTree::SeedLabel(if b {Label::True} else {Label::False}))),
                                 _ =>  {
                                     self.compilation.add_error("Invalid argument type", None);
                                     None
@@ -393,7 +1060,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a == b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a == b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for eq function. Expected 2 integer parameters.", call_location);
                             None
@@ -403,7 +1070,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a != b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a != b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for ne function. Expected 2 integer parameters.", call_location);
                             None
@@ -413,7 +1080,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a > b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a > b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for gt function. Expected 2 integer parameters.", call_location);
                             None
@@ -423,7 +1090,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a >= b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a >= b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for gte function. Expected 2 integer parameters.", call_location);
                             None
@@ -433,7 +1100,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a < b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a < b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for lt function. Expected 2 integer parameters.", call_location);
                             None
@@ -443,7 +1110,7 @@ impl<'a> AtomTreeTranslator<'a> {
                         if application.len() == 2 {
                             let b = application.pop()?.get_as_int_or_error(self.compilation)?;
                             let a = application.pop()?.get_as_int_or_error(self.compilation)?;
-                            Some(ValueCollection::Single(AtomTree::AtomType {atom: if a <= b { AtomType::True} else {AtomType::False}} ))
+                            Some(ValueCollection::Super(SuperValue::Bool(a <= b)))
                         } else {
                             self.compilation.add_error("Incorrect parameters for lte function. Expected 2 integer parameters.", call_location);
                             None
@@ -499,10 +1166,18 @@ impl<'a> AtomTreeTranslator<'a> {
             SubLocation::Atom(a) => {
                 match a.value {
                     AtomSub::Not => {
+                        if let Some(b) = application.get_as_super_bool() {
+                            return Some(ValueCollection::Super(SuperValue::Bool(!b)));
+                        }
                         let application = application.get_as_atom_tree_if_single_or_error(self.compilation)?;
                         Some(ValueCollection::Single(AtomTree::Not(application.into())))
                     },
                     AtomSub::Or => {
+                        if let ValueCollection::Tuple(t) = &application {
+                            if let [Some(a), Some(b)] = t.iter().map(|v| v.get_as_super_bool()).collect::<Vec<_>>()[..] {
+                                return Some(ValueCollection::Super(SuperValue::Bool(a || b)));
+                            }
+                        }
                         let applications = match application {
                             ValueCollection::Tuple(mut t) if t.len() == 2 => {
                                 t.into_iter().map(|v| v.get_as_atom_tree_if_single_or_error(self.compilation).unwrap_or_default()).collect()
@@ -518,37 +1193,66 @@ impl<'a> AtomTreeTranslator<'a> {
                     }
                 }
             }
-            SubLocation::Structure { collection, sub } => {
-                let collection_ref = self.collections.iter().find(|&f| f.name.value == collection.value);
-                let collection_ref = match collection_ref {
-                    None => {
-                        self.compilation.add_error(&format!("Couldn't find collection \"{}\"", collection.value), collection.location.to_owned());
+            SubLocation::Reference(name) => {
+                let (collection, sub) = match variables.get(&name.value) {
+                    Some(ValueCollection::SubReference { collection, sub }) => (collection.clone(), sub.clone()),
+                    Some(_) => {
+                        self.compilation.add_error(&format!("{} is not a sub", name.value), name.location.clone());
                         return None;
                     }
-                    Some(collection) => collection
-                };
-                let sub_ref = collection_ref.subs.iter().find(|&f| f.name.value == sub.value);
-                let sub_ref = match sub_ref {
                     None => {
-                        self.compilation.add_error(&format!("Couldn't find sub in collection: \"{}\"", collection.value), collection.location.to_owned());
+                        self.compilation.add_error(&format!("Variable {} not found in current scope.", name.value), name.location.clone());
                         return None;
                     }
-                    Some(collection) => collection
-                }.clone(); //Cloned because we would need to borrow self as mut later.
+                };
+                //References are always resolved, so the location of the call is used for errors
+                let sub_ref = self.find_sub(&LocationValue::new(name.location.clone(), collection), &LocationValue::new(name.location.clone(), sub))?;
+                let application = if let ValueCollection::Tuple(t) = application {
+                    t
+                } else {
+                    vec![application]
+                };
+                self.compile_substructure(&sub_ref, application, vec![], call_location)
+            }
+            SubLocation::Structure { collection, sub } => {
+                let sub_ref = self.find_sub(collection, sub)?;
 
                 let application = if let ValueCollection::Tuple(t) = application {
                     t
                 } else {
                     vec![application]
                 };
-                self.compile_substructure(&sub_ref, application)
+                let mut generics = vec![];
+                for generic in &sub_call_syntax.generics {
+                    generics.push(self.compile_expression(generic, variables)?.get_as_int_or_error(self.compilation)?);
+                }
+                self.compile_substructure(&sub_ref, application, generics, call_location)
 
             }
-        
+            SubLocation::Method(method) => {
+                let ValueCollection::Tuple(application) = application else {
+                    self.compilation.add_error("Internal: Expected the receiver of the method call in the application", call_location);
+                    return None;
+                };
+                let type_name = match application.first() {
+                    Some(ValueCollection::Composite { composite_name, .. }) => composite_name,
+                    Some(ValueCollection::Enum { enum_name, .. }) => enum_name,
+                    _ => {
+                        self.compilation.add_error(&format!("Only composites and enums have methods, can't call \"{}\"", method.value), method.location.clone());
+                        return None;
+                    }
+                };
+                let Some((_, sub_ref)) = find_method(&self.collections, type_name, &method.value) else {
+                    self.compilation.add_error(&format!("Type {type_name} has no method \"{}\"", method.value), method.location.clone());
+                    return None;
+                };
+                let sub_ref = sub_ref.clone();
+                self.compile_substructure(&sub_ref, application, vec![], call_location)
+            }
         }
     }
     
-    pub fn compile_expression(&mut self, value: &ExpressionSyntax, variables: &mut HashMap<String, ValueCollection>) -> Option<ValueCollection> {
+    pub fn compile_expression(&mut self, value: &ExpressionSyntax, variables: &mut Scope<ValueCollection>) -> Option<ValueCollection> {
         let compilation = unsafe {self.extract_compilation()};
         match value {
             ExpressionSyntax::Array(expressions) => {
@@ -572,7 +1276,31 @@ impl<'a> AtomTreeTranslator<'a> {
             ExpressionSyntax::String(string) => {
                 Some(ValueCollection::Super(SuperValue::String(string.value.to_owned())))
             }
+            //Computed values, like the result of a.add(b), can be accessed but aren't places that could be written to
+            ExpressionSyntax::Access { base, field } if !base.is_place() => {
+                self.compile_expression(base, variables)?.access_identifier_or_error(field, compilation).cloned()
+            }
+            ExpressionSyntax::AccessIdx { base, idx } if !base.is_place() => {
+                self.compile_expression(base, variables)?.access_index_or_error(&idx.value, compilation).cloned()
+            }
+            ExpressionSyntax::IndexOp { base, index } if !base.is_place() => {
+                let index = self.compile_expression(index, variables)?.get_as_int_or_error(self.compilation)?;
+                self.compile_expression(base, variables)?.access_indexed_or_error(&index, compilation).cloned()
+            }
             ExpressionSyntax::Access{base, field} => {
+                //Variants of enums, like Color.Red
+                if let ExpressionSyntax::Variable(name) = base.as_ref() {
+                    if variables.get(&name.value).is_none() && !self.supers.contains_key(&name.value) {
+                        if let Some(enum_syntax) = self.find_enum(&name.value) {
+                            let Some(index) = enum_syntax.variant_index(&field.value) else {
+                                self.compilation.add_error(&format!("Enum {} has no variant \"{}\"", name.value, field.value), field.location.clone());
+                                return None;
+                            };
+                            let bits = enum_syntax.encode(index).into_iter().map(|bit| ValueCollection::Super(SuperValue::Bool(bit))).collect();
+                            return Some(ValueCollection::Enum { enum_name: name.value.clone(), bits });
+                        }
+                    }
+                }
                 self.compile_access_expression(&base, variables)?.access_identifier_or_error(&field, compilation).cloned() 
 
             }
@@ -591,19 +1319,33 @@ impl<'a> AtomTreeTranslator<'a> {
                         return None;
                     }
                 };
+                let composite_generics = composite.generics.clone();
                 let mut missing_fields = composite.fields.clone();
                 let mut assigned_fields = HashMap::new();
+                //Generics of the composite are inferred from the assigned fields
+                let mut generics = Scope::new();
                 for field_assign in field_assign {
                     match missing_fields.iter().enumerate().find(|(_, n)| n.name.value == field_assign.left.value) {
                         Some((i, _)) => {
-                            missing_fields.remove(i);
-                            assigned_fields.insert(field_assign.left.value.clone(), self.compile_expression(&field_assign.right, variables)?);
+                            let field = missing_fields.remove(i);
+                            let value = self.compile_expression(&field_assign.right, variables)?;
+                            if let Err(e) = self.match_type(&value, &field.type_syntax, &composite_generics, &mut generics) {
+                                self.compilation.add_error(&format!("Field {} of {} expects {e}", field.name.value, type_name.value), field_assign.right.code_location());
+                            }
+                            assigned_fields.insert(field_assign.left.value.clone(), value);
                         },
                         None => {
                             self.compilation.add_error(&format!("Field \"{}\" has either already been assigned, or is not in the composite type.", field_assign.left.value), field_assign.left.location.clone());
                         }
                     }
                 }
+                for field in missing_fields.iter().filter(|field| field.can_be_omitted()) {
+                    //Defaults are evaluated like supers, without access to the variables of the constructor, but they can use the generics of the composite
+                    if let Some(default) = &field.default {
+                        assigned_fields.insert(field.name.value.clone(), self.compile_expression(default, &mut generics)?);
+                    }
+                }
+                missing_fields.retain(|field| !field.can_be_omitted());
                 if !missing_fields.is_empty() {
                     self.compilation.add_error("Some fields of the struct haven't been assigned", type_name.location.clone());
                     return None;
@@ -616,6 +1358,8 @@ impl<'a> AtomTreeTranslator<'a> {
             ExpressionSyntax::Variable(name) => {
                 if let Some(var) = variables.get(&name.value) {
                     Some(var.to_owned())
+                } else if self.supers.contains_key(&name.value) {
+                    self.evaluate_super(&name.value).map(ValueCollection::Super)
                 } else {
                     self.compilation.add_error(&format!("Variable {} not found in current scope.", name.value), name.location.clone());
                     None
@@ -632,7 +1376,25 @@ impl<'a> AtomTreeTranslator<'a> {
             ExpressionSyntax::Sub(sub_call_syntax) => {
                 self.compile_sub_call(sub_call_syntax, variables)
             }
-            _ => todo!("Compiling {:?} hasn't been implemented", value)
+            ExpressionSyntax::SubReference { collection, sub } => {
+                self.find_sub(collection, sub)?;
+                Some(ValueCollection::SubReference { collection: collection.value.clone(), sub: sub.value.clone() })
+            }
+            ExpressionSyntax::Equality { operator, left, right } => {
+                let a = self.compile_expression(left, variables)?;
+                let b = self.compile_expression(right, variables)?;
+                let negated = operator.value.is_negated();
+                match (a, b) {
+                    (ValueCollection::Super(SuperValue::Int(a)), ValueCollection::Super(SuperValue::Int(b))) if operator.value != EqualityOperator::Xor => {
+                        Some(ValueCollection::Super(SuperValue::Bool((a == b) != negated)))
+                    }
+                    (a, b) => match self.compile_eq(a, b)? {
+                        eq if !negated => Some(eq),
+                        ValueCollection::Super(SuperValue::Bool(eq)) => Some(ValueCollection::Super(SuperValue::Bool(!eq))),
+                        eq => Some(ValueCollection::Single(AtomTree::Not(eq.get_as_atom_tree_if_single_or_error(compilation)?.into())))
+                    }
+                }
+            }
         }
         
     }
@@ -646,7 +1408,7 @@ impl<'a> AtomTreeTranslator<'a> {
         s
     }
 
-    pub fn compile_access_expression<'b>(&mut self, value: &ExpressionSyntax, variables: &'b mut HashMap<String, ValueCollection>) -> Option<&'b mut ValueCollection> {
+    pub fn compile_access_expression<'b>(&mut self, value: &ExpressionSyntax, variables: &'b mut Scope<ValueCollection>) -> Option<&'b mut ValueCollection> {
         let compilation = unsafe {self.extract_compilation()};
         match value {
             ExpressionSyntax::Access { base, field } => {
@@ -666,6 +1428,9 @@ impl<'a> AtomTreeTranslator<'a> {
             ExpressionSyntax::Variable(name) => {
                 if let Some(var) = variables.get_mut(&name.value) {
                     Some(var)
+                } else if self.supers.contains_key(&name.value) {
+                    compilation.add_error(&format!("Super {} is a constant and can't be reassigned.", name.value), name.location.clone());
+                    None
                 } else {
                     compilation.add_error(&format!("Variable {} not found in current scope.", name.value), name.location.clone());
                     None
@@ -680,6 +1445,38 @@ impl<'a> AtomTreeTranslator<'a> {
     }
 }
 
+//Whether the code writes to the variable or a part of it, which decides if a for loop writes the elements of an array back
+fn writes_to(code: &[CodeSyntax], name: &str) -> bool {
+    code.iter().any(|statement| match statement {
+        CodeSyntax::ReassignSyntax { variable, .. } => root_variable(variable) == Some(name),
+        CodeSyntax::ReassignPattern { pattern, .. } => pattern_writes_to(pattern, name),
+        //Nested loops can write to an array through their own loop variable
+        CodeSyntax::For { iterator_variable, iterator, iterator_body, .. } => {
+            writes_to(iterator_body, name) || matches!(iterator, IteratorSyntax::Value(array) if root_variable(array) == Some(name) && writes_to(iterator_body, &iterator_variable.value))
+        }
+        CodeSyntax::If { condition_true, .. } => writes_to(condition_true, name),
+        CodeSyntax::IfElse { condition_true, condition_false, .. } => writes_to(condition_true, name) || writes_to(condition_false, name),
+        CodeSyntax::Match { arms, .. } => arms.iter().any(|arm| writes_to(&arm.code, name)),
+        _ => false
+    })
+}
+
+fn pattern_writes_to(pattern: &PatternSyntax, name: &str) -> bool {
+    match pattern {
+        PatternSyntax::Single(variable) => root_variable(variable) == Some(name),
+        PatternSyntax::Tuple { elements, .. } => elements.iter().any(|element| pattern_writes_to(element, name)),
+        PatternSyntax::Composite { fields, .. } => fields.iter().any(|(_, field)| pattern_writes_to(field, name)),
+    }
+}
+
+fn root_variable(expression: &ExpressionSyntax) -> Option<&str> {
+    match expression {
+        ExpressionSyntax::Variable(name) => Some(&name.value),
+        ExpressionSyntax::Access { base, .. } | ExpressionSyntax::AccessIdx { base, .. } | ExpressionSyntax::IndexOp { base, .. } => root_variable(base),
+        _ => None
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 pub enum ValueCollection {
     #[default]
@@ -694,13 +1491,34 @@ pub enum ValueCollection {
         composite_name: String,
         fields: HashMap<String, ValueCollection>
     },
-    Super(SuperValue)
+    //The booleans encoding a variant of the enum
+    Enum {
+        enum_name: String,
+        bits: Vec<ValueCollection>
+    },
+    Super(SuperValue),
+    //A sub that can be called at compile time, it is inlined like any other call
+    SubReference {
+        collection: String,
+        sub: String
+    }
 }
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 
 pub enum SuperValue {
     String(String),
-    Int(usize)
+    Int(usize),
+    Bool(bool),
+}
+
+impl std::fmt::Display for SuperValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{s}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Bool(b) => write!(f, "{b}"),
+        }
+    }
 }
 
 impl ValueCollection {
@@ -723,6 +1541,14 @@ impl ValueCollection {
             }
         }
     }
+    //Compile time booleans, possibly wrapped in a single element tuple
+    pub fn get_as_super_bool(&self) -> Option<bool> {
+        match self {
+            Self::Super(SuperValue::Bool(b)) => Some(*b),
+            Self::Tuple(t) if t.len() == 1 => t[0].get_as_super_bool(),
+            _ => None
+        }
+    }
     pub fn get_as_atom_type_or_error(self, compilation: &mut Compilation) -> Option<AtomType> {
         match self {
             Self::Single(AtomTree::AtomType { atom }) => Some(atom),
@@ -741,10 +1567,20 @@ impl ValueCollection {
             }
         }
     }
+    pub fn as_atom_tree(&self) -> Option<AtomTree> {
+        match self {
+            Self::SingleVar(id) => Some(AtomTree::Variable { id: *id }),
+            Self::Single(tree) => Some(tree.clone()),
+            Self::Super(SuperValue::Bool(b)) => Some(AtomTree::AtomType { atom: if *b { AtomType::True } else { AtomType::False } }),
+            Self::Tuple(t) if t.len() == 1 => t[0].as_atom_tree(),
+            _ => None
+        }
+    }
     pub fn get_as_atom_tree_if_single_or_error(self, compilation: &mut Compilation) -> Option<AtomTree> {
         match self {
             Self::SingleVar(id) => Some(AtomTree::Variable { id }),
             Self::Single(tree) => Some(tree),
+            Self::Super(SuperValue::Bool(b)) => Some(AtomTree::AtomType { atom: if b { AtomType::True } else { AtomType::False } }),
             Self::Tuple(mut t) if t.len() == 1 => t.remove(0).get_as_atom_tree_if_single_or_error(compilation),
             _ => {
                 compilation.add_error("Expected simple boolean value", None);
@@ -770,6 +1606,9 @@ impl ValueCollection {
                             .collect() 
                     }
             }
+            Self::Enum { enum_name, bits } => {
+                Self::Enum { enum_name, bits: bits.into_iter().map(|bit| bit.write_as_var(atom_tree_translate)).collect() }
+            }
             Self::SingleVar(s) => Self::SingleVar(s),
             _ => self
         }
@@ -781,7 +1620,7 @@ impl ValueCollection {
                     if let Some(field) = fields.get_mut(&accessor_name.value) {
                         Some(field)
                     } else {
-                        compilation.add_error(&format!("Field \"{}\" not found in composite type", accessor_name.value), accessor_name.location.clone());
+                        compilation.add_error(&format!("Field \"{}\" not found in composite type. Optional fields can only be accessed if they were assigned", accessor_name.value), accessor_name.location.clone());
                         None
                     }
                 },
@@ -839,3 +1678,327 @@ impl ValueCollection {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use crate::compiler::{compile_in_memory, compile_source, diagnostic::DiagnosticPipelineLocation, settings::Settings};
+
+    #[test]
+    fn early_return() {
+        let output = compile_source(r#"
+            collection c {
+                sub pick(bool: a, bool: b) {
+                    if (a) {
+                        return (true, b)
+                    }
+                    output "not returned"
+                } = (false, not b)
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    output c::pick(a, b)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(True, False, )".to_string()]);
+    }
+
+    #[test]
+    fn enum_match() {
+        let output = compile_source(r#"
+            enum Color: binary { Red, Green, Blue }
+            problem {
+                sub test(bool: a, Color: c) {
+                    let x = Color.Red
+                    if (a) {
+                        x = c
+                    }
+                    match (x) {
+                        Red => { output "red" }
+                        else => { output "other" }
+                    }
+                    output x == Color.Blue
+                    output (x, Color.Green)
+                }
+            }
+            solution {
+                test(true, Color.Blue)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["other".to_string(), "True".to_string(), "(Color.Blue, Color.Green, )".to_string()]);
+
+        //Bits that aren't known are printed as they are
+        let output = compile_source(r#"
+            enum Color: binary { Red, Green, Blue }
+            problem {
+                sub test(Color: c) {
+                    output c
+                }
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["Color(Null, Null, )".to_string()]);
+    }
+
+    #[test]
+    fn structured_problem_inputs() {
+        let output = compile_source(r#"
+            import std::int
+            composite Pair {
+                [bool; 2]: a,
+                bool: flag
+            }
+            problem {
+                sub test(Int<4>: x, Pair: p) {
+                    output x.data
+                    output (p.a, p.flag)
+                }
+            }
+            solution {
+                test(0x6, Pair { a: [false, true], flag: true })
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["[False, True, True, False, ]".to_string(), "([False, True, ], True, )".to_string()]);
+    }
+
+    #[test]
+    fn witnesses() {
+        let output = compile_source(r#"
+            problem {
+                sub find(bool: a) {
+                    witness bool: secret
+                    force (a ^ secret) => true
+                    output secret
+                }
+            }
+            solution {
+                find(true) witness { secret: false },
+                find(true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["False".to_string()]);
+        assert_eq!(output.further_solutions[0].outputs, vec!["Null".to_string()]);
+    }
+
+    #[test]
+    fn for_loops() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: x, bool: y) {
+                    let a = [x, y, false]
+                    for (b in a) {
+                        b = not b
+                    }
+                    output a
+                    for ((i, b) in enumerate(a)) {
+                        output (i, b)
+                    }
+                    for (i in 1..8 step 3) {
+                        output i
+                    }
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["[False, True, True, ]".to_string(), "(0, False, )".to_string(), "(1, True, )".to_string(), "(2, True, )".to_string(), "1".to_string(), "4".to_string(), "7".to_string()]);
+    }
+
+    #[test]
+    fn if_scope() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: b) {
+                    let x = a
+                    if (b) {
+                        let x = not a
+                        let y = x
+                        output y
+                    }
+                    output x
+                }
+            }
+            solution {
+                test(true, true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["False".to_string(), "True".to_string()]);
+
+        //The type checker reports the same error, so it's ignored to see what the translator does
+        let settings = Settings { ignore_errors: true, ..Default::default() };
+        let files = HashMap::from([(PathBuf::from("main.coala"), r#"
+            problem {
+                sub test(bool: a) {
+                    if (a) {
+                        let y = a
+                    }
+                    output y
+                }
+            }
+            solution {
+                test(true)
+            }"#.to_string())]);
+        let output = compile_in_memory(&settings, &files);
+        assert!(output.diagnostics.iter().any(|d| matches!(d.pipeline_location, DiagnosticPipelineLocation::Access) && d.description.contains("Variable y not found")), "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn computed_supers() {
+        let output = compile_source(r#"
+            super BYTES = super::mul(WORDS, WORD)
+            super WORD = 4
+            super WORDS = super::add(1, 1)
+            super NAME = super::concat("block", "s")
+            problem {
+                sub test(bool: a) {
+                    output BYTES
+                    output NAME
+                    output [a; super::div(BYTES, 4)]
+                }
+            }
+            solution {
+                test(true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["8".to_string(), "blocks".to_string(), "[True, True, ]".to_string()]);
+
+        let output = compile_source(r#"
+            super A = super::add(B, 1)
+            super B = A
+            problem {
+                sub test(bool: a) {
+                    output A
+                }
+            }"#);
+        assert!(output.diagnostics.iter().any(|d| d.description == "Super A depends on itself"), "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn reassigned_super_bool() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: c) {
+                    let e = super::eq(1, 1)
+                    let f = super::eq(1, 2)
+                    if (c) {
+                        e = a
+                    }
+                    f = a
+                    output (e, f)
+                }
+            }
+            solution {
+                test(false, true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(False, False, )".to_string()]);
+    }
+
+    #[test]
+    fn conditional_reassignment() {
+        let output = compile_source(r#"
+            composite Pair {
+                bool: a,
+                [bool; 2]: b
+            }
+            problem {
+                sub test(bool: x, bool: c) {
+                    let p = Pair { a: x, b: [x, false] }
+                    let q = [x, x, x]
+                    if (c) {
+                        p = Pair { a: not x, b: [false, not x] }
+                        q = [false, x, false]
+                    }
+                    output (p.a, p.b, q)
+                    if (not c) {
+                        p = Pair { a: false, b: [false, false] }
+                        q = [false, false, false]
+                    }
+                    output (p.a, p.b, q)
+                }
+            }
+            solution {
+                test(true, true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(False, [False, False, ], [False, True, False, ], )".to_string(), "(False, [False, False, ], [False, True, False, ], )".to_string()]);
+
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: x, bool: c) {
+                    let q = [x, x]
+                    if (c) {
+                        q = [x, x, x]
+                    }
+                }
+            }
+            solution {
+                test(true, true)
+            }"#);
+        let diagnostic = output.diagnostics.iter().find(|d| d.description == "Can't select between values of different shapes or compile time values at runtime").expect("Expected a shape error");
+        assert!(diagnostic.location.is_some(), "{:?}", diagnostic);
+    }
+
+    #[test]
+    fn array_length_mismatch() {
+        let output = compile_source(r#"
+            collection c {
+                sub first([bool; 32]: data) {} = data[0]
+            }
+            problem {
+                sub test(bool: a) {
+                    output c::first([a; 32])
+                    output c::first([a; 31])
+                }
+            }
+            solution {
+                test(true)
+            }"#);
+        let diagnostic = output.diagnostics.iter().find(|d| d.description == "Argument data of first expects an array of length 32, found 31").expect("Expected a length error");
+        assert!(diagnostic.location.is_some(), "{:?}", diagnostic);
+        assert_eq!(output.diagnostics.iter().filter(|d| d.description.starts_with("Argument data")).count(), 1, "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn destructuring() {
+        let output = compile_source(r#"
+            composite Pair {
+                bool: left,
+                (bool, bool): right
+            }
+            problem {
+                sub test(bool: a, bool: b, bool: c) {
+                    let (x, y) = (a, b)
+                    let ((p, q), r) = ((a, b), c)
+                    let Pair { left, right: (m, n) } = Pair { left: a, right: (b, c) }
+                    output (x, y, p, q, r, left, m, n)
+
+                    (x, y) = (y, x)
+                    Pair { left: m, right: (n, left) } = Pair { left: c, right: (a, b) }
+                    output (x, y, left, m, n)
+
+                    let arr = [a, b]
+                    if (c) {
+                        (arr[0], x) = (b, c)
+                    }
+                    if (not c) {
+                        (arr[1], y) = (a, c)
+                    }
+                    output (arr, x, y)
+                }
+            }
+            solution {
+                test(true, false, true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec![
+            "(True, False, True, False, True, True, False, True, )".to_string(),
+            "(False, True, False, True, True, )".to_string(),
+            "([False, False, ], True, True, )".to_string(),
+        ]);
+    }
+}
@@ -173,12 +173,24 @@ impl TokenBlockType<'_> {
     pub fn is_double_colon(&self) -> bool {
         self.as_delimiter().map(|d| d.is_double_colon()).is_some_and(|s| s)
     }
+    pub fn is_question_mark(&self) -> bool {
+        self.as_delimiter().map(|d| d.is_question_mark()).is_some_and(|s| s)
+    }
+    pub fn is_less_than(&self) -> bool {
+        self.as_delimiter().map(|d| d.is_less_than()).is_some_and(|s| s)
+    }
+    pub fn is_equals(&self) -> bool {
+        self.as_delimiter().map(|d| d.is_equals()).is_some_and(|s| s)
+    }
     pub fn is_period(&self) -> bool {
         self.as_delimiter().map(|d| d.is_period()).is_some_and(|s| s)
     }
     pub fn is_curly_block(&self) -> bool {
         self.as_block().map(|d| d.is_curly()).is_some_and(|s| s)
     }
+    pub fn is_round_block(&self) -> bool {
+        self.as_block().map(|d| d.is_round()).is_some_and(|s| s)
+    }
 }
 
 impl TokenBlock {
@@ -2,16 +2,190 @@ use std::collections::HashSet;
 
 use crate::compiler::atom_tree_to_graph::{Label, Node};
 
-pub fn export_as_csv(nodes: &Vec<Node>, buf_edges: &mut String, buf_labels: &mut String) {
-    buf_labels.push_str("Id,Label,Color\n");
-    for (i, node) in nodes.iter().enumerate() {
-        match node.label {
-            Label::False => buf_labels.push_str(&format!("{i},FALSE,#FF0000\n")),
-            Label::True => buf_labels.push_str(&format!("{i},TRUE,#00FF00\n")),
-            Label::Neutral => buf_labels.push_str(&format!("{i},NEUTRAL,#0000FF\n")),
-            Label::Null => buf_labels.push_str(&format!("{i},NULL,#555555\n")),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Cnf,
+    Col,
+    GraphMl,
+    Gexf,
+    Dot,
+}
+
+impl ExportFormat {
+    pub const NAMES: &'static [&'static str] = &["csv", "cnf", "col", "graphml", "gexf", "dot"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "cnf" => Some(Self::Cnf),
+            "col" => Some(Self::Col),
+            "graphml" => Some(Self::GraphMl),
+            "gexf" => Some(Self::Gexf),
+            "dot" => Some(Self::Dot),
+            _ => None
+        }
+    }
 
+    pub fn exporter(&self) -> Box<dyn Exporter> {
+        match self {
+            Self::Csv => Box::new(CsvExporter),
+            Self::Cnf => Box::new(CnfExporter),
+            Self::Col => Box::new(ColExporter),
+            Self::GraphMl => Box::new(GraphMlExporter),
+            Self::Gexf => Box::new(GexfExporter),
+            Self::Dot => Box::new(DotExporter),
         }
+    }
+}
+
+pub trait Exporter {
+    ///Exports the graph. Returns the exported files as pairs of a suffix for the output file name and the file contents.
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)>;
+}
+
+///Two files readable by gephi, one for the edges and one for the labels.
+pub struct CsvExporter;
+///SAT instance of the coloring problem in the DIMACS CNF format.
+pub struct CnfExporter;
+///The graph in the DIMACS .col format used by graph coloring benchmarks.
+pub struct ColExporter;
+///GraphML, an XML format read by yEd, Cytoscape and most graph libraries.
+pub struct GraphMlExporter;
+///Gephi's native format, with the labels as node colors.
+pub struct GexfExporter;
+///Graphviz DOT.
+pub struct DotExporter;
+
+impl Exporter for CsvExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let mut buf_edges = String::new();
+        let mut buf_labels = String::new();
+        export_as_csv(nodes, &mut buf_edges, &mut buf_labels);
+        vec![("_edges.csv", buf_edges), ("_labels.csv", buf_labels)]
+    }
+}
+
+impl Exporter for CnfExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let mut buf = String::new();
+        export_as_cnf(nodes, &mut buf);
+        vec![(".cnf", buf)]
+    }
+}
+
+impl Exporter for ColExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let edges = edges(nodes);
+        let mut buf = String::new();
+        buf.push_str("c Coala graph coloring instance\n");
+        buf.push_str(&format!("p edge {} {}\n", nodes.len(), edges.len()));
+        for (a, b) in edges {
+            //DIMACS nodes start at 1
+            buf.push_str(&format!("e {} {}\n", a + 1, b + 1));
+        }
+        vec![(".col", buf)]
+    }
+}
+
+impl Exporter for GraphMlExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let mut buf = String::new();
+        buf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        buf.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        buf.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+        buf.push_str("  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>\n");
+        buf.push_str("  <graph id=\"coala\" edgedefault=\"undirected\">\n");
+        for (i, node) in nodes.iter().enumerate() {
+            buf.push_str(&format!("    <node id=\"n{i}\"><data key=\"label\">{}</data><data key=\"color\">{}</data></node>\n", label_name(node.label), label_color(node.label)));
+        }
+        for (a, b) in edges(nodes) {
+            buf.push_str(&format!("    <edge source=\"n{a}\" target=\"n{b}\"/>\n"));
+        }
+        buf.push_str("  </graph>\n");
+        buf.push_str("</graphml>\n");
+        vec![(".graphml", buf)]
+    }
+}
+
+impl Exporter for GexfExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let mut buf = String::new();
+        buf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        buf.push_str("<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n");
+        buf.push_str("  <graph defaultedgetype=\"undirected\">\n");
+        buf.push_str("    <nodes>\n");
+        for (i, node) in nodes.iter().enumerate() {
+            let (r, g, b) = label_rgb(node.label);
+            buf.push_str(&format!("      <node id=\"{i}\" label=\"{}\"><viz:color r=\"{r}\" g=\"{g}\" b=\"{b}\"/></node>\n", label_name(node.label)));
+        }
+        buf.push_str("    </nodes>\n");
+        buf.push_str("    <edges>\n");
+        for (i, (a, b)) in edges(nodes).into_iter().enumerate() {
+            buf.push_str(&format!("      <edge id=\"{i}\" source=\"{a}\" target=\"{b}\"/>\n"));
+        }
+        buf.push_str("    </edges>\n");
+        buf.push_str("  </graph>\n");
+        buf.push_str("</gexf>\n");
+        vec![(".gexf", buf)]
+    }
+}
+
+impl Exporter for DotExporter {
+    fn export(&self, nodes: &[Node]) -> Vec<(&'static str, String)> {
+        let mut buf = String::new();
+        buf.push_str("graph coala {\n");
+        for (i, node) in nodes.iter().enumerate() {
+            buf.push_str(&format!("    {i} [label=\"{}\", style=filled, fillcolor=\"{}\"];\n", label_name(node.label), label_color(node.label)));
+        }
+        for (a, b) in edges(nodes) {
+            buf.push_str(&format!("    {a} -- {b};\n"));
+        }
+        buf.push_str("}\n");
+        vec![(".dot", buf)]
+    }
+}
+
+//Every edge of the graph exactly once, with the smaller node first.
+pub(crate) fn edges(nodes: &[Node]) -> Vec<(usize, usize)> {
+    let mut edges = vec![];
+    for (i, node) in nodes.iter().enumerate() {
+        let mut set: Vec<_> = node.connections.iter().copied().filter(|&connection| connection > i).collect::<HashSet<_>>().into_iter().collect();
+        set.sort();
+        for connection in set {
+            edges.push((i, connection));
+        }
+    }
+    edges
+}
+
+pub(crate) fn label_name(label: Label) -> &'static str {
+    match label {
+        Label::False => "FALSE",
+        Label::True => "TRUE",
+        Label::Neutral => "NEUTRAL",
+        Label::Null => "NULL",
+    }
+}
+
+fn label_rgb(label: Label) -> (u8, u8, u8) {
+    match label {
+        Label::False => (0xFF, 0, 0),
+        Label::True => (0, 0xFF, 0),
+        Label::Neutral => (0, 0, 0xFF),
+        Label::Null => (0x55, 0x55, 0x55),
+    }
+}
+
+fn label_color(label: Label) -> String {
+    let (r, g, b) = label_rgb(label);
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
+
+pub fn export_as_csv(nodes: &[Node], buf_edges: &mut String, buf_labels: &mut String) {
+    buf_labels.push_str("Id,Label,Color\n");
+    for (i, node) in nodes.iter().enumerate() {
+        buf_labels.push_str(&format!("{i},{},{}\n", label_name(node.label), label_color(node.label)));
         let set: HashSet<_> = node.connections.iter().collect();
         buf_edges.push_str(&format!("{i}")); 
         for &elem in set {
@@ -19,4 +193,188 @@ pub fn export_as_csv(nodes: &Vec<Node>, buf_edges: &mut String, buf_labels: &mut
         }
         buf_edges.push('\n');
     }
-}
\ No newline at end of file
+}
+
+const CNF_COLORS: [Label; 3] = [Label::True, Label::False, Label::Neutral];
+
+//Variable of the SAT instance that is true iff the node has the color with the given index in CNF_COLORS
+fn cnf_variable(node: usize, color: usize) -> usize {
+    node * CNF_COLORS.len() + color + 1
+}
+
+///Encodes the coloring instance of the graph as a SAT instance in the DIMACS CNF format.
+///There is one variable per node and color. The labels of the nodes aren't included, so the instance doesn't give away the solution.
+pub fn export_as_cnf(nodes: &[Node], buf: &mut String) {
+    let mut clauses: Vec<Vec<isize>> = vec![];
+    for (i, node) in nodes.iter().enumerate() {
+        //Every node has at least one color...
+        clauses.push((0..CNF_COLORS.len()).map(|c| cnf_variable(i, c) as isize).collect());
+        //...and at most one.
+        for a in 0..CNF_COLORS.len() {
+            for b in (a + 1)..CNF_COLORS.len() {
+                clauses.push(vec![-(cnf_variable(i, a) as isize), -(cnf_variable(i, b) as isize)]);
+            }
+        }
+        for (c, color) in CNF_COLORS.iter().enumerate() {
+            if !node.label_whitelist.contains(color) {
+                clauses.push(vec![-(cnf_variable(i, c) as isize)]);
+            }
+        }
+        let set: HashSet<_> = node.connections.iter().filter(|&&connection| connection > i).collect();
+        for &connection in set {
+            for c in 0..CNF_COLORS.len() {
+                clauses.push(vec![-(cnf_variable(i, c) as isize), -(cnf_variable(connection, c) as isize)]);
+            }
+        }
+    }
+
+    buf.push_str("c Coala graph coloring instance\n");
+    buf.push_str("c Variable 3n+1 is node n colored TRUE, 3n+2 node n colored FALSE and 3n+3 node n colored NEUTRAL\n");
+    buf.push_str(&format!("p cnf {} {}\n", nodes.len() * CNF_COLORS.len(), clauses.len()));
+    for clause in clauses {
+        for literal in clause {
+            buf.push_str(&format!("{literal} "));
+        }
+        buf.push_str("0\n");
+    }
+}
+
+///Decodes a model of the CNF instance produced by `export_as_cnf` back into node labels.
+///Accepts the usual SAT competition output (`s` and `v` lines) as well as a plain list of literals.
+pub fn decode_cnf_model(model: &str, node_count: usize) -> Result<Vec<Label>, String> {
+    let mut labels = vec![Label::Null; node_count];
+    for line in model.lines() {
+        let line = line.trim();
+        if line.starts_with('c') {
+            continue;
+        }
+        if let Some(status) = line.strip_prefix('s') {
+            if status.trim() != "SATISFIABLE" {
+                return Err(format!("The SAT solver didn't find a model ({})", status.trim()));
+            }
+            continue;
+        }
+        let literals = line.strip_prefix('v').unwrap_or(line);
+        for literal in literals.split_whitespace() {
+            let literal: isize = match literal.parse() {
+                Ok(l) => l,
+                Err(_) => return Err(format!("Invalid literal \"{literal}\" in model")),
+            };
+            if literal <= 0 {
+                continue;
+            }
+            let variable = literal as usize - 1;
+            let node = variable / CNF_COLORS.len();
+            if node >= node_count {
+                return Err(format!("Variable {literal} doesn't belong to any node of the graph"));
+            }
+            if labels[node] != Label::Null {
+                return Err(format!("Node {node} has more than one color in the model"));
+            }
+            labels[node] = CNF_COLORS[variable % CNF_COLORS.len()];
+        }
+    }
+    if let Some(node) = labels.iter().position(|label| *label == Label::Null) {
+        return Err(format!("Node {node} has no color in the model"));
+    }
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::solver;
+
+    //A triangle with a pendant node. The connection from 1 to 0 is listed twice, edges are only exported once
+    fn handmade_graph() -> Vec<Node> {
+        let connections: [&[usize]; 4] = [&[1, 2], &[0, 0, 2], &[0, 1, 3], &[2]];
+        connections.iter().map(|connections| Node {
+            connections: connections.to_vec(),
+            label: Label::Null,
+            label_whitelist: CNF_COLORS.iter().copied().collect(),
+        }).collect()
+    }
+
+    fn export(format: ExportFormat) -> Vec<(&'static str, String)> {
+        format.exporter().export(&handmade_graph())
+    }
+
+    fn count(text: &str, pattern: &str) -> usize {
+        text.matches(pattern).count()
+    }
+
+    #[test]
+    fn csv_export() {
+        let files = export(ExportFormat::Csv);
+        assert_eq!(files.iter().map(|f| f.0).collect::<Vec<_>>(), vec!["_edges.csv", "_labels.csv"]);
+        assert_eq!(files[0].1.lines().count(), 4);
+        assert_eq!(files[1].1.lines().count(), 5);
+    }
+
+    #[test]
+    fn col_export() {
+        let files = export(ExportFormat::Col);
+        assert!(files[0].1.contains("p edge 4 4\n"));
+        assert_eq!(count(&files[0].1, "\ne "), 4);
+    }
+
+    #[test]
+    fn graphml_export() {
+        let files = export(ExportFormat::GraphMl);
+        assert_eq!(files[0].0, ".graphml");
+        assert_eq!(count(&files[0].1, "<node "), 4);
+        assert_eq!(count(&files[0].1, "<edge "), 4);
+    }
+
+    #[test]
+    fn gexf_export() {
+        let files = export(ExportFormat::Gexf);
+        assert_eq!(files[0].0, ".gexf");
+        assert_eq!(count(&files[0].1, "<node "), 4);
+        assert_eq!(count(&files[0].1, "<edge "), 4);
+    }
+
+    #[test]
+    fn dot_export() {
+        let files = export(ExportFormat::Dot);
+        assert_eq!(files[0].0, ".dot");
+        assert_eq!(count(&files[0].1, "[label="), 4);
+        assert_eq!(count(&files[0].1, " -- "), 4);
+    }
+
+    #[test]
+    fn cnf_round_trip() {
+        let nodes = handmade_graph();
+        let mut cnf = String::new();
+        export_as_cnf(&nodes, &mut cnf);
+
+        let labels = solver::solve(&nodes).expect("The graph is colorable");
+        let literals: Vec<isize> = labels.iter().enumerate().flat_map(|(i, label)| {
+            CNF_COLORS.iter().enumerate().map(move |(c, color)| if color == label { cnf_variable(i, c) as isize } else { -(cnf_variable(i, c) as isize) })
+        }).collect();
+        //The model has to satisfy every clause of the instance
+        for clause in cnf.lines().filter(|line| !line.starts_with('c') && !line.starts_with('p')) {
+            let clause: Vec<isize> = clause.split_whitespace().map(|l| l.parse().unwrap()).take_while(|&l| l != 0).collect();
+            assert!(clause.iter().any(|l| literals.contains(l)), "Unsatisfied clause {clause:?}");
+        }
+
+        let model = format!("s SATISFIABLE\nv {} 0\n", literals.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(" "));
+        let decoded = decode_cnf_model(&model, nodes.len()).expect("The model is valid");
+        assert_eq!(decoded, labels);
+        let mut nodes = nodes;
+        for (node, label) in nodes.iter_mut().zip(decoded) {
+            node.label = label;
+        }
+        assert!(solver::verify(&nodes).is_empty());
+    }
+
+    #[test]
+    fn malformed_cnf_models() {
+        assert!(decode_cnf_model("s UNSATISFIABLE\n", 2).unwrap_err().contains("didn't find a model"));
+        assert!(decode_cnf_model("v 1 x 0\n", 2).unwrap_err().contains("Invalid literal \"x\""));
+        assert!(decode_cnf_model("v 7 0\n", 2).unwrap_err().contains("doesn't belong to any node"));
+        assert!(decode_cnf_model("v 1 2 0\n", 2).unwrap_err().contains("more than one color"));
+        assert_eq!(decode_cnf_model("v 1 -4 -5 -6 0\n", 2).unwrap_err(), "Node 1 has no color in the model");
+        assert_eq!(decode_cnf_model("c comment\n1 -2 -3 6 0\n", 2).unwrap(), vec![Label::True, Label::Neutral]);
+    }
+}
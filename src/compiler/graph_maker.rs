@@ -36,40 +36,6 @@ pub struct GlobalSymbolTable {
 }
 
 impl GraphMaker {
-    pub fn output_as_adjacency_list(&self, output_count: bool) {
-        if output_count {println!("{}", self.nodes.len());}
-        
-        for node in &self.nodes {
-            let set: HashSet<_> = node.connections.iter().collect();
-            if output_count {print!("{}", set.len());}
-            for &elem in set {
-                print!("{elem} ")
-            }
-            println!()
-        }
-    }
-
-    pub fn export_as_csv(&self, buf: &mut String) {
-        for (i, node) in self.nodes.iter().enumerate() {
-            let set: HashSet<_> = node.connections.iter().collect();
-            buf.push_str(&format!("{i}")); 
-            for &elem in set {
-                buf.push_str(&format!(";{elem}"));
-            }
-            buf.push('\n');
-        }
-    }
-
-    pub fn output_as_adjacency_matrix(&self) {
-        for node in &self.nodes {
-            let set: HashSet<_> = node.connections.iter().collect();
-
-            for i in 0..self.nodes.len() {
-                print!("{}, ", if set.contains(&i) {1} else {0})
-            }
-            println!()
-        }
-    }
     pub fn compile(&mut self, compilation: &mut Compilation) {
         if let Some(problems) = std::mem::replace(&mut self.project.problems, Some(vec![]))  {
             for problem in problems {
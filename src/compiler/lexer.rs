@@ -29,21 +29,28 @@ static KEYWORD_MAPPING: phf::Map<&'static str, &'static TokenType> = phf_map! {
     
     "problem" => &TokenType::Keyword(Keyword::Problem),
     "solution" => &TokenType::Keyword(Keyword::Solution),
+    "witness" => &TokenType::Keyword(Keyword::Witness),
 
     "let" => &TokenType::Keyword(Keyword::Let),
     "force" => &TokenType::Keyword(Keyword::Force),
     "if" => &TokenType::Keyword(Keyword::If),
     "else" => &TokenType::Keyword(Keyword::Else),
     "for" => &TokenType::Keyword(Keyword::For),
+    "match" => &TokenType::Keyword(Keyword::Match),
     "in" => &TokenType::Keyword(Keyword::In),
 
 
     "output" => &TokenType::Keyword(Keyword::Output),
+    "return" => &TokenType::Keyword(Keyword::Return),
+    "static_assert" => &TokenType::Keyword(Keyword::StaticAssert),
+    "compile_error" => &TokenType::Keyword(Keyword::CompileError),
 
 
     "sub" => &TokenType::Keyword(Keyword::SubStructure),
     "collection" => &TokenType::Keyword(Keyword::Collection),
+    "impl" => &TokenType::Keyword(Keyword::Impl),
     "composite" => &TokenType::Keyword(Keyword::Composite),
+    "enum" => &TokenType::Keyword(Keyword::Enum),
 
 
     "true" => &TokenType::Atom(Atom::Type(AtomType::True)),
@@ -76,8 +83,27 @@ static DELIM_MAPPING: phf::Map<&'static str, &'static TokenType> = phf_map! {
     ";" => &TokenType::Delimiter(Delimiter::Semicolon),
     "," => &TokenType::Delimiter(Delimiter::Comma),
     "." => &TokenType::Delimiter(Delimiter::Period),
+    ".." => &TokenType::Delimiter(Delimiter::DoublePeriod),
 
     "=" => &TokenType::Delimiter(Delimiter::Equals),
+    "?" => &TokenType::Delimiter(Delimiter::QuestionMark),
+    "<" => &TokenType::Delimiter(Delimiter::LessThan),
+    ">" => &TokenType::Delimiter(Delimiter::GreaterThan),
+    "<=" => &TokenType::Delimiter(Delimiter::LessThanOrEquals),
+    ">=" => &TokenType::Delimiter(Delimiter::GreaterThanOrEquals),
+    "==" => &TokenType::Delimiter(Delimiter::DoubleEquals),
+    "!=" => &TokenType::Delimiter(Delimiter::NotEquals),
+
+    "!" => &TokenType::Delimiter(Delimiter::ExclamationMark),
+    "&" => &TokenType::Delimiter(Delimiter::Ampersand),
+    "|" => &TokenType::Delimiter(Delimiter::Pipe),
+    "^" => &TokenType::Delimiter(Delimiter::Caret),
+
+    "+" => &TokenType::Delimiter(Delimiter::Plus),
+    "-" => &TokenType::Delimiter(Delimiter::Minus),
+    "*" => &TokenType::Delimiter(Delimiter::Star),
+    "/" => &TokenType::Delimiter(Delimiter::Slash),
+    "%" => &TokenType::Delimiter(Delimiter::Percent),
 
 };
 
@@ -1,14 +1,16 @@
-use std::{fs::File, io::Write, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf};
 
-use atom_tree_to_graph::AtomTreeCompiler;
+use atom_tree::AtomRoot;
+use atom_tree_to_graph::{AtomTreeCompiler, Label, Node};
 use atom_tree_translate::AtomTreeTranslator;
+use code_location::{CodeLocation, LocationValue};
 use compilation::Compilation;
-use diagnostic::Diagnostic;
+use diagnostic::{Diagnostic, DiagnosticType};
 use file_reader::FileReader;
 use parser::Parser;
 use settings::Settings;
 use string_file_reader::StringFileReader;
-use syntax::ImportSyntax;
+use syntax::{ExpressionSyntax, ImportSyntax, Project};
 
 pub mod code_location;
 pub mod export;
@@ -24,12 +26,13 @@ mod string_file_reader;
 mod type_stream;
 mod parser;
 mod atom_tree_translate;
-mod atom_tree_to_graph;
+pub mod atom_tree_to_graph;
 mod block_parser;
 mod lib_embed;
 pub mod typecheck;
 pub mod scope;
 pub mod atom_tree_to_expr;
+pub mod solver;
 
 
 
@@ -94,101 +97,299 @@ pub fn to_path(base_path: &PathBuf, import_syntax: &ImportSyntax) -> Result<Path
     Ok(path)
 }
 pub fn parse_file<T: FileReader>(file: &PathBuf, internal: bool, file_reader: &mut T, parser: &mut Parser, settings: &Settings) {
-    println!("Reading file ({:?})...", file);
+    log(settings, &format!("Reading file ({:?})...", file));
     if !internal {
         if let Err(diagnostic) = file_reader.reset_to_file(file) {
             parser.compilation.add_diagnostic(diagnostic);
             return;
         }
     }
-    println!("Lexing file ({:?})...", file);
-    let tokens = lexer::tokenize(file_reader, file, parser.compilation).unwrap();
-    println!("Preparsing file ({:?})...", file);
-    let mut tokens = block_parser::TokenBlock::from_token_stream(tokens, parser.compilation).unwrap();
-    println!("Parsing file ({:?})...", file);
+    log(settings, &format!("Lexing file ({:?})...", file));
+    //Lexing and block errors are already reported, the file is then skipped
+    let Some(tokens) = lexer::tokenize(file_reader, file, parser.compilation) else {
+        return;
+    };
+    log(settings, &format!("Preparsing file ({:?})...", file));
+    let Ok(mut tokens) = block_parser::TokenBlock::from_token_stream(tokens, parser.compilation) else {
+        return;
+    };
+    log(settings, &format!("Parsing file ({:?})...", file));
     parser.parse_file(&mut tokens);
 }
-pub fn compile(settings: &Settings) {
-    println!("Loading project...");
-    let project = &settings.base_path;
-    let mut compilation = Compilation::new(settings.to_owned());
-    let file: String;
-    match project {
-        Some(s) => {file = s.to_owned();}
-        None => {
-            compilation.add_error("No project file was specified", None);
-            end_compilation(settings, &compilation);
+//Writes the graph in every requested format into the output directory, creating the directory if needed.
+fn write_outputs(settings: &Settings, output_name: &str, nodes: &[Node], compilation: &mut Compilation) {
+    let directory = PathBuf::from(settings.output_directory.as_deref().unwrap_or("./"));
+    if let Err(e) = std::fs::create_dir_all(&directory) {
+        compilation.add_diagnostic(Diagnostic::new(
+            diagnostic::DiagnosticType::Error,
+            format!("Couldn't create output directory {:?}.\nInternal error: {e}", directory),
+            Some(CodeLocation::new(directory)),
+            diagnostic::DiagnosticPipelineLocation::IO
+        ));
+        return;
+    }
+    for format in &settings.emit {
+        for (suffix, contents) in format.exporter().export(nodes) {
+            let path = directory.join(format!("{output_name}{suffix}"));
+            if let Err(e) = std::fs::write(&path, contents) {
+                compilation.add_diagnostic(Diagnostic::new(
+                    diagnostic::DiagnosticType::Error,
+                    format!("Couldn't write output file {:?}.\nInternal error: {e}", path),
+                    Some(CodeLocation::new(path)),
+                    diagnostic::DiagnosticPipelineLocation::IO
+                ));
+            }
+        }
+    }
+}
+//Replaces the labels of the graph with the ones of a SAT solver model for the CNF export. Returns whether the labels were replaced.
+fn decode_model(model_path: &str, nodes: &mut [Node], compilation: &mut Compilation) -> bool {
+    let model = match std::fs::read_to_string(model_path) {
+        Ok(m) => m,
+        Err(e) => {
+            compilation.add_diagnostic(Diagnostic::new(
+                diagnostic::DiagnosticType::Error,
+                format!("Couldn't read model file '{model_path}'.\nInternal error: {e}"),
+                None,
+                diagnostic::DiagnosticPipelineLocation::IO
+            ));
+            return false;
+        }
+    };
+    match export::decode_cnf_model(&model, nodes.len()) {
+        Ok(labels) => {
+            for (node, label) in nodes.iter_mut().zip(labels) {
+                node.label = label;
+            }
+            true
+        }
+        Err(e) => {
+            compilation.add_error(&format!("Couldn't decode model: {e}"), None);
+            false
+        }
+    }
+}
+//Verifies the coloring produced by the compiler or decoded from a model, and searches for one if the graph is run in solve mode.
+//An invalid decoded coloring is an error, since the model doesn't belong to the graph.
+fn check_coloring(settings: &Settings, decoded: bool, nodes: &mut [Node], compilation: &mut Compilation) {
+    if settings.solve {
+        log(settings, "Solving graph...");
+        if solver::verify(nodes).is_empty() {
+            compilation.add_info("The compiled coloring is already valid.", None);
             return;
         }
+        match solver::solve(nodes) {
+            Some(labels) => {
+                for (node, label) in nodes.iter_mut().zip(labels) {
+                    node.label = label;
+                }
+                compilation.add_info("Found a valid coloring of the graph.", None);
+            }
+            None => {
+                compilation.add_error("The graph can't be colored with the given restrictions and inputs.", None);
+            }
+        }
+    } else if nodes.iter().all(|node| node.label != Label::Null) {
+        let errors = solver::verify(nodes);
+        match errors.first() {
+            Some(error) if decoded => compilation.add_error(&format!("The decoded coloring is invalid ({} violations). {}", errors.len(), error.describe()), None),
+            Some(error) => compilation.add_warning(&format!("The compiled coloring is invalid ({} violations). {}", errors.len(), error.describe()), None),
+            None => {}
+        }
+    }
+}
+///The result of compiling a project without touching the file system.
+#[derive(Debug)]
+pub struct CompileOutput {
+    pub nodes: Vec<Node>,
+    pub atom_tree: AtomRoot,
+    pub diagnostics: Vec<Diagnostic>,
+    //Text printed by the output statements whose conditions were met
+    pub outputs: Vec<String>,
+    //The graphs of the further solutions, if a problem has more than one. The fields above belong to the first solution
+    pub further_solutions: Vec<SolutionOutput>,
+}
 
+///The graph compiled for one solution of the project
+#[derive(Debug)]
+pub struct SolutionOutput {
+    pub nodes: Vec<Node>,
+    pub outputs: Vec<String>,
+}
+
+impl CompileOutput {
+    pub fn is_error_free(&self) -> bool {
+        self.diagnostics.iter().all(|d| d.type_lower_than(DiagnosticType::Error))
     }
-    let base_path: PathBuf = file.into();
-    let mut parser = Parser::new(&mut compilation);
-    let mut file_reader = StringFileReader::new();
+}
 
-    let mut main_file = base_path.clone();
-    main_file.push("main.coala");
+//Where the source files of a project are read from
+enum Sources<'a> {
+    Directory(PathBuf),
+    Memory(&'a HashMap<PathBuf, String>),
+}
 
-    if main_file.is_file() { 
+fn log(settings: &Settings, message: &str) {
+    if settings.output_progress {
+        println!("{message}");
+    }
+}
 
-        parse_file(&main_file, false, &mut file_reader,  &mut parser, settings);
-    } else {
-        compilation.add_error("No main file found. Please make sure your base directory contains a file called \"main.coala\"", None);
-        end_compilation(settings, &compilation);
+fn parse_std_import(import_syntax: &ImportSyntax, file_reader: &mut StringFileReader, parser: &mut Parser, settings: &Settings) {
+    let mut path = String::new();
+    import_syntax.path.iter().skip(1).for_each(|part| {
+        if path.is_empty() {
+            path.push_str(&part.value);
+        } else {
+            path.push_str(&format!("/{}", part.value));
+        }
+    });
+    path.push_str(".coala");
+    let file = lib_embed::get_std_lib_file(&path);
+    if file.is_none() {
+        parser.compilation.add_error(&format!("Couldn't find standard library file {path}"), None);
         return;
     }
+    let file = file.unwrap();
+    file_reader.reset_to_string(&file);
+    parse_file(&PathBuf::from(format!("std/{path}")), true, file_reader, parser, settings);
+}
+
+//Parses the main file and everything it imports
+fn parse_project(sources: &Sources, settings: &Settings, compilation: &mut Compilation) -> Option<Project> {
+    let mut parser = Parser::new(compilation);
+    let mut file_reader = StringFileReader::new();
+
+    match sources {
+        Sources::Directory(base_path) => {
+            let mut main_file = base_path.clone();
+            main_file.push("main.coala");
+
+            if main_file.is_file() { 
+                parse_file(&main_file, false, &mut file_reader,  &mut parser, settings);
+            } else {
+                parser.compilation.add_error("No main file found. Please make sure your base directory contains a file called \"main.coala\"", None);
+                return None;
+            }
+        }
+        Sources::Memory(files) => {
+            let main_file = PathBuf::from("main.coala");
+            match files.get(&main_file) {
+                Some(source) => {
+                    file_reader.reset_to_string(source);
+                    parse_file(&main_file, true, &mut file_reader, &mut parser, settings);
+                }
+                None => {
+                    parser.compilation.add_error("No main file found. Please make sure the sources contain a file called \"main.coala\"", None);
+                    return None;
+                }
+            }
+        }
+    }
 
     while let Some((import_syntax, _)) = parser.imports.iter().find(|f| !f.1) {
         let import_syntax = import_syntax.clone();
         *parser.imports.get_mut(&import_syntax).unwrap() = true;
         if import_syntax.path.first().unwrap().value == "std" {
-            let mut path = String::new();
-            import_syntax.path.iter().skip(1).for_each(|part| {
-                if path.is_empty() {
-                    path.push_str(&format!("{}", part.value));
-                } else {
-                    path.push_str(&format!("/{}", part.value));
+            parse_std_import(&import_syntax, &mut file_reader, &mut parser, settings);
+            continue;
+        }
+        match sources {
+            Sources::Directory(base_path) => {
+                let file = match to_path(base_path, &import_syntax) {
+                    Ok(path) => path,
+                    Err(diagnostic) => {
+                        parser.compilation.add_diagnostic(diagnostic);
+                        continue;
+                    }
+                };
+                parse_file(&file, false, &mut file_reader, &mut parser, settings);
+            }
+            Sources::Memory(files) => {
+                let mut file = PathBuf::new();
+                for part in &import_syntax.path {
+                    file.push(&part.value);
+                }
+                file.set_extension("coala");
+                match files.get(&file) {
+                    Some(source) => {
+                        file_reader.reset_to_string(source);
+                        parse_file(&file, true, &mut file_reader, &mut parser, settings);
+                    }
+                    None => {
+                        let location = import_syntax.path.last().unwrap().location.clone();
+                        parser.compilation.add_diagnostic(Diagnostic::new(
+                            DiagnosticType::Error,
+                            format!("Couldn't find imported file {:?}", file),
+                            location,
+                            diagnostic::DiagnosticPipelineLocation::IO
+                        ));
+                    }
                 }
-            });
-            path.push_str(".coala");
-            let file = lib_embed::get_std_lib_file(&path);
-            if file.is_none() {
-                parser.compilation.add_error(&format!("Couldn't find standard library file {path}"), None);
-                continue;
             }
-            let file = file.unwrap();
-            file_reader.reset_to_string(&file);
-            parse_file(&PathBuf::from(format!("std/{path}")), true, &mut file_reader, &mut parser, settings);
+        }
+    }
+
+    Some(parser.project)
+}
+
+//Applies the super overrides of the settings and type checks the project
+fn check_project(project: &mut Project, settings: &Settings, compilation: &mut Compilation) {
+    for (name, value) in &settings.super_overrides {
+        match project.supers.get_mut(name) {
+            //Values that aren't integers are taken as strings
+            Some(super_syntax) => super_syntax.value = match value.parse::<usize>() {
+                Ok(int) => ExpressionSyntax::Int(LocationValue::new(None, int)),
+                Err(_) => ExpressionSyntax::String(LocationValue::new(None, value.to_owned())),
+            },
+            None => compilation.add_warning(&format!("Super \"{name}\" is overridden, but never declared"), None),
+        }
+    }
+    log(settings, "Type checking project...");
+    typecheck::type_check(project, compilation);
+}
+
+//Compiles a graph for every solution of the project. Diagnostics of the further solutions are only kept if they weren't already reported for an earlier one.
+fn build_graphs(project: Project, settings: &Settings, compilation: &mut Compilation) -> Vec<(AtomRoot, Vec<Node>, Vec<String>)> {
+    let count = project.solution_count();
+    let mut graphs = vec![];
+    for index in 0..count {
+        if index == 0 {
+            graphs.push(build_graph(project.clone(), index, settings, compilation));
             continue;
         }
-        let file = match to_path(&base_path, &import_syntax) {
-            Ok(path) => path,
-            Err(diagnostic) => {
-                parser.compilation.add_diagnostic(diagnostic);
-                continue;
+        log(settings, &format!("Compiling solution {}...", index + 1));
+        let mut solution_compilation = Compilation::new(settings.to_owned());
+        graphs.push(build_graph(project.clone(), index, settings, &mut solution_compilation));
+        for diagnostic in solution_compilation.diagnostics() {
+            let reported = compilation.diagnostics().iter().any(|d| d.description == diagnostic.description && d.location == diagnostic.location);
+            if !reported {
+                compilation.add_diagnostic(diagnostic.to_owned());
             }
-        };
-        
-        parse_file(&file, false, &mut file_reader, &mut parser, settings);
+        }
     }
+    graphs
+}
 
-    let project = parser.project;
-    if settings.print_debug_logs {
-        println!("Project: {:#?}", project);
+//The output name of a solution, the first solution keeps the name of the settings
+fn solution_output_name(settings: &Settings, index: usize) -> String {
+    if index == 0 {
+        settings.output_name.to_owned()
+    } else {
+        format!("{}_{}", settings.output_name, index + 1)
     }
+}
 
-    if !(settings.ignore_errors || compilation.is_error_free()) {
-        end_compilation(settings, &compilation);
-        return;
-    }
-    println!("Compiling project to IR...");
-    let atom_tree_translator = AtomTreeTranslator::new(&mut compilation, project.collections, project.composite_types);
-    let mut atom_tree = atom_tree_translator.convert(project.problems, project.solutions);
+//Translates the project to IR, optimizes it and compiles it to a graph. Returns the final IR, the graph and the results of the output statements.
+fn build_graph(project: Project, solution: usize, settings: &Settings, compilation: &mut Compilation) -> (AtomRoot, Vec<Node>, Vec<String>) {
+    log(settings, "Compiling project to IR...");
+    let atom_tree_translator = AtomTreeTranslator::new(compilation, project.collections, project.composite_types, project.enums, project.supers);
+    let mut atom_tree = atom_tree_translator.convert(project.problems, &project.solutions, solution);
     if settings.print_debug_logs {
         println!("{:#?}", atom_tree);
     }
     if settings.optimize {
-        println!("Optimizing, removing links...");
+        log(settings, "Optimizing, removing links...");
 
         while atom_tree.remove_links() {
             if settings.print_debug_logs {
@@ -196,10 +397,10 @@ pub fn compile(settings: &Settings) {
             }
         }
         let mut i = 0;
-        println!("Optimizing, simplifying {i}. run, {} definitions and {} value actions...", atom_tree.definitions.len(), atom_tree.value_actions.len());
+        log(settings, &format!("Optimizing, simplifying {i}. run, {} definitions and {} value actions...", atom_tree.definitions.len(), atom_tree.value_actions.len()));
         i += 1;
-        while atom_tree.simp_all(&mut compilation) {
-            println!("Optimizing, simplifying {i}. run, {} definitions and {} value actions...", atom_tree.definitions.len(), atom_tree.value_actions.len());
+        while atom_tree.simp_all(compilation) {
+            log(settings, &format!("Optimizing, simplifying {i}. run, {} definitions and {} value actions...", atom_tree.definitions.len(), atom_tree.value_actions.len()));
 
             if settings.print_debug_logs {
                 println!("{:#?}", atom_tree);
@@ -212,22 +413,22 @@ pub fn compile(settings: &Settings) {
             i += 1;
         }
         if settings.heavy_optimization {
-            println!("Heavy optimization enabled, inlining all...");
+            log(settings, "Heavy optimization enabled, inlining all...");
             atom_tree.inline_all();
-            println!("Second pass of simplification...");
-            while atom_tree.simp_all(&mut compilation) {
+            log(settings, "Second pass of simplification...");
+            while atom_tree.simp_all(compilation) {
                 if settings.print_debug_logs {
                     //println!("{:#?}", atom_tree);
                 }    
             }
         }
-        println!("Outlining common expressions");
+        log(settings, "Outlining common expressions");
         atom_tree.outline_common_expressions();
         if settings.print_debug_logs {
             println!("{:#?}", atom_tree);
         }
-        println!("Next pass of simplification...");
-        while atom_tree.simp_all(&mut compilation) {
+        log(settings, "Next pass of simplification...");
+        while atom_tree.simp_all(compilation) {
                 if settings.print_debug_logs {
                     println!("{:#?}", atom_tree);
                 }
@@ -239,24 +440,236 @@ pub fn compile(settings: &Settings) {
             }
         
     }
-    println!("Finalizing IR simplification...");
+    log(settings, "Finalizing IR simplification...");
     atom_tree.finalize_simp();
     if settings.print_debug_logs {
         println!("{:#?}", atom_tree);
     }
-    println!("Compiling and running IR...");
+    log(settings, "Compiling and running IR...");
+
+    let atom_tree_compiler = AtomTreeCompiler::new(atom_tree.clone());
+    let graph = atom_tree_compiler.compile();
+    if graph.unsolvable_restrictions {
+        compilation.add_warning("Node restrictions made graph unsolvable.", None);
+    }
+    (atom_tree, graph.nodes, graph.outputs)
+}
+
+///Compiles a project from a set of source files held in memory. The main file has to be called `main.coala`, imports are resolved relative to it
+///(`import folder::file` refers to `folder/file.coala`). Nothing is read from or written to disk, and nothing is printed.
+pub fn compile_in_memory(settings: &Settings, files: &HashMap<PathBuf, String>) -> CompileOutput {
+    let mut settings = settings.to_owned();
+    settings.output_progress = false;
+    settings.print_debug_logs = false;
+    let mut compilation = Compilation::new(settings.to_owned());
+
+    let mut output = CompileOutput { nodes: vec![], atom_tree: AtomRoot::default(), diagnostics: vec![], outputs: vec![], further_solutions: vec![] };
+    if let Some(mut project) = parse_project(&Sources::Memory(files), &settings, &mut compilation) {
+        check_project(&mut project, &settings, &mut compilation);
+        if settings.ignore_errors || compilation.is_error_free() {
+            for (index, (atom_tree, mut nodes, outputs)) in build_graphs(project, &settings, &mut compilation).into_iter().enumerate() {
+                check_coloring(&settings, false, &mut nodes, &mut compilation);
+                if index == 0 {
+                    output.atom_tree = atom_tree;
+                    output.nodes = nodes;
+                    output.outputs = outputs;
+                } else {
+                    output.further_solutions.push(SolutionOutput { nodes, outputs });
+                }
+            }
+        }
+    }
+    output.diagnostics = compilation.diagnostics().to_owned();
+    output
+}
+
+///Compiles a project consisting of only a main file, for the tests of the compiler stages
+#[cfg(test)]
+pub(crate) fn compile_source(source: &str) -> CompileOutput {
+    let files = HashMap::from([(PathBuf::from("main.coala"), source.to_string())]);
+    compile_in_memory(&Settings::default(), &files)
+}
+
+pub fn compile(settings: &Settings) {
+    log(settings, "Loading project...");
+    let project = &settings.base_path;
+    let mut compilation = Compilation::new(settings.to_owned());
+    let file: String;
+    match project {
+        Some(s) => {file = s.to_owned();}
+        None => {
+            compilation.add_error("No project file was specified", None);
+            end_compilation(settings, &compilation);
+            return;
+        }
 
-    let atom_tree_compiler = AtomTreeCompiler::new(atom_tree);
-    let nodes = atom_tree_compiler.compile();
-    //println!("{:#?}:{}", nodes, nodes.len());
-    println!("Exporting compilation results...");
+    }
+    let base_path: PathBuf = file.into();
+    let mut project = match parse_project(&Sources::Directory(base_path), settings, &mut compilation) {
+        Some(p) => p,
+        None => {
+            end_compilation(settings, &compilation);
+            return;
+        }
+    };
+    if settings.print_debug_logs {
+        println!("Project: {:#?}", project);
+    }
+    check_project(&mut project, settings, &mut compilation);
 
-    let mut buf_edges = String::new();
-    let mut buf_labels = String::new();
+    if !(settings.ignore_errors || compilation.is_error_free()) {
+        end_compilation(settings, &compilation);
+        return;
+    }
+    let graphs = build_graphs(project, settings, &mut compilation);
+    let solution_count = graphs.len();
+    for (index, (_, mut nodes, outputs)) in graphs.into_iter().enumerate() {
+        if solution_count > 1 {
+            println!("Solution {}:", index + 1);
+        }
+        for output in outputs {
+            println!("{output}");
+        }
+        //println!("{:#?}:{}", nodes, nodes.len());
+        //The node order differs between the graphs, so a model only belongs to the first one
+        let decoded = match (&settings.decode_model, index) {
+            (Some(model_path), 0) => decode_model(model_path, &mut nodes, &mut compilation),
+            _ => false
+        };
+        check_coloring(settings, decoded, &mut nodes, &mut compilation);
+        log(settings, "Exporting compilation results...");
 
-    export::export_as_csv(&nodes, &mut buf_edges, &mut buf_labels);
-    File::create("./compiled_edges.csv").unwrap().write(buf_edges.as_bytes()).unwrap();
-    File::create("./compiled_labels.csv").unwrap().write(buf_labels.as_bytes()).unwrap();
+        write_outputs(settings, &solution_output_name(settings, index), &nodes, &mut compilation);
+    }
 
     end_compilation(settings, &compilation);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_source() {
+        let unbalanced = r#"
+            problem {
+                sub test(bool: a) {
+                    output (a
+                }
+            }"#;
+        let unterminated = r#"
+            problem {
+                sub test(bool: a) {
+                    output "a
+                }
+            }"#;
+        //The first error of every source, the stage that found it and the text it points at
+        let cases = [
+            (unbalanced, "Expected Round", "Access", "}"),
+            (unterminated, "Expected '\" to mark end of string'.", "Lexing", "\"a"),
+            ("problem { ) }", "Expected Curly", "Access", ")"),
+        ];
+        for (source, description, stage, text) in cases {
+            let output = compile_source(source);
+            assert!(output.nodes.is_empty());
+            let diagnostic = output.diagnostics.first().expect(source);
+            assert_eq!(diagnostic.description, description);
+            assert_eq!(format!("{:?}", diagnostic.pipeline_location), stage);
+            let section = diagnostic.location.as_ref().and_then(|l| l.section.as_ref()).expect(description);
+            assert!(source[section.location_begin as usize..].starts_with(text), "{diagnostic:?}");
+        }
+    }
+
+    #[test]
+    fn write_outputs_to_directory() {
+        let directory = std::env::temp_dir().join(format!("coala_write_outputs_{}", std::process::id()));
+        _ = std::fs::remove_dir_all(&directory);
+        let nodes = vec![
+            Node { connections: vec![1], label: Label::True, label_whitelist: [Label::True].into() },
+            Node { connections: vec![0], label: Label::False, label_whitelist: [Label::False].into() },
+        ];
+
+        //Missing directories are created
+        let mut settings = Settings { output_directory: Some(directory.join("nested").to_string_lossy().into_owned()), ..Settings::default() };
+        let mut compilation = Compilation::new(settings.clone());
+        write_outputs(&settings, "graph", &nodes, &mut compilation);
+        assert!(compilation.is_error_free(), "{:?}", compilation.diagnostics());
+        assert!(directory.join("nested/graph_edges.csv").is_file());
+        assert!(directory.join("nested/graph_labels.csv").is_file());
+
+        //A directory in place of an output file can't be written
+        std::fs::create_dir_all(directory.join("nested/blocked_edges.csv")).unwrap();
+        write_outputs(&settings, "blocked", &nodes, &mut compilation);
+        assert!(compilation.diagnostics().iter().any(|d| d.description.starts_with("Couldn't write output file") && matches!(d.pipeline_location, diagnostic::DiagnosticPipelineLocation::IO)), "{:?}", compilation.diagnostics());
+
+        //Neither can a file in place of the output directory
+        settings.output_directory = Some(directory.join("nested/graph_edges.csv").to_string_lossy().into_owned());
+        let mut compilation = Compilation::new(settings.clone());
+        write_outputs(&settings, "graph", &nodes, &mut compilation);
+        assert!(compilation.diagnostics().iter().any(|d| d.description.starts_with("Couldn't create output directory")), "{:?}", compilation.diagnostics());
+
+        _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn multiple_solutions() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: b) {
+                    output a | b
+                }
+            }
+            solution {
+                test(true, false),
+                test(false, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["True".to_string()]);
+        assert_eq!(output.further_solutions.len(), 1);
+        assert_eq!(output.further_solutions[0].outputs, vec!["False".to_string()]);
+
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: b) {
+                }
+            }
+            solution {
+                test(true),
+                tset(true, false)
+            }"#);
+        assert!(output.diagnostics.iter().any(|d| d.description.contains("expects 2 arguments, but the solution gives 1")), "{:?}", output.diagnostics);
+        assert!(output.diagnostics.iter().any(|d| d.description.contains("unknown problem \"tset\"")), "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn super_overrides() {
+        let source = r#"
+            super WIDTH = 2
+            super NAME = "default"
+            problem {
+                sub test(bool: a) {
+                    let bits = [a; WIDTH]
+                    output bits
+                    output NAME
+                }
+            }
+            solution {
+                test(true)
+            }"#;
+        let files = HashMap::from([(PathBuf::from("main.coala"), source.to_string())]);
+        let settings = Settings { super_overrides: vec![("WIDTH".into(), "3".into()), ("NAME".into(), "overridden".into()), ("MISSING".into(), "1".into())], ..Settings::default() };
+        let output = compile_in_memory(&settings, &files);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["[True, True, True, ]".to_string(), "overridden".to_string()]);
+        assert!(output.diagnostics.iter().any(|d| d.description == "Super \"MISSING\" is overridden, but never declared"), "{:?}", output.diagnostics);
+
+        let output = compile_source(r#"
+            super WIDTH = 2
+            super WIDTH = 3
+            problem {
+                sub test(bool: a) {
+                }
+            }"#);
+        assert!(output.diagnostics.iter().any(|d| d.description == "The name of super \"WIDTH\" is already in use"), "{:?}", output.diagnostics);
+    }
+}
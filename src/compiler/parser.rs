@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 
-use crate::compiler::{block_parser::{Block, TokenBlock, TokenBlockType}, compilation::Compilation, diagnostic::{Diagnostic, DiagnosticPipelineLocation, DiagnosticType}, syntax::{CodeSyntax, CollectionSyntax, CompositeTypeSyntax, ExpressionSyntax, FieldAssignSyntax, SubCallSyntax, SubLocation, SubstructureSyntax, TypeSyntax, TypedIdentifierSyntax}, token::{Atom, Brace, Delimiter, Keyword, TokenType}, type_stream::TypeStream};
+use crate::compiler::{block_parser::{Block, TokenBlock, TokenBlockType}, compilation::Compilation, diagnostic::{Diagnostic, DiagnosticPipelineLocation, DiagnosticType}, syntax::{CodeSyntax, CollectionSyntax, CompositeFieldSyntax, CompositeTypeSyntax, EnumEncoding, EnumSyntax, EqualityOperator, ExpressionSyntax, FieldAssignSyntax, IteratorSyntax, MatchArmSyntax, PatternSyntax, SolutionSyntax, SubCallSyntax, SubLocation, SubstructureSyntax, SuperSyntax, TypeSyntax, TypedIdentifierSyntax, span}, token::{Atom, AtomSub, Brace, Delimiter, Keyword, TokenType}, type_stream::TypeStream};
 
-use super::{code_location::LocationValue, syntax::{ImportSyntax, Project}};
+use super::{code_location::{CodeLocation, LocationValue}, syntax::{ImportSyntax, Project}};
 
 
 pub struct Parser<'a> {
     pub compilation: &'a mut Compilation,
     pub imports: HashMap<ImportSyntax, bool>,
     pub project: Project,
+    //Variables of the sub being parsed that hold a sub reference, a round block after one of them calls it
+    sub_references: Vec<String>,
 }
 
 
@@ -17,7 +19,8 @@ impl<'a> Parser<'a> {
         Self {
             compilation,
             project: Project::new(),
-            imports: HashMap::default()
+            imports: HashMap::default(),
+            sub_references: vec![]
         }
     }
 
@@ -71,6 +74,16 @@ impl<'a> Parser<'a> {
                         self.project.collections.push(c);
                     }
                 }
+                //impl Type { subs } adds methods to a type, they are also callable as Type::sub
+                TokenBlockType::Token(TokenType::Keyword(Keyword::Impl)) => {
+                    let Some(mut c) = self.parse_collection(token_stream) else {
+                        continue;
+                    };
+                    match self.project.collections.iter_mut().find(|existing| existing.name.value == c.name.value) {
+                        Some(existing) => existing.subs.append(&mut c.subs),
+                        None => self.project.collections.push(c)
+                    }
+                }
                 TokenBlockType::Token(TokenType::Keyword(Keyword::Problem)) => {
                     if let None = token_stream.error_if_empty(self.compilation, "code block") {
                         continue;
@@ -85,6 +98,9 @@ impl<'a> Parser<'a> {
                 TokenBlockType::Token(TokenType::Keyword(Keyword::Composite)) => {
                     self.parse_composite(token_stream);
                 }
+                TokenBlockType::Token(TokenType::Keyword(Keyword::Enum)) => {
+                    self.parse_enum(token_stream);
+                }
                 TokenBlockType::Token(TokenType::EOF) => return,
                 _ => {            
                     self.compilation.add_error(&format!("Unexpected token at file level: \"{:?}\"", current_token.token_type()), Some(current_token.code_location().to_owned()));
@@ -97,15 +113,54 @@ impl<'a> Parser<'a> {
 
         let identifier = token_stream.next().into_identifier_or_error(self.compilation)?;
 
+        let generics = self.parse_generic_parameters(token_stream)?;
+
         token_stream.error_if_empty(self.compilation, "code block")?;
 
         let composite_body = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
 
-        let fields = self.parse_typed_identifiers(composite_body)?;
+        let fields = self.parse_typed_identifiers(composite_body, true)?;
+
+        self.project.composite_types.push(CompositeTypeSyntax { name: identifier, generics, fields });
+
+
+        Some(())
+    }
+    //enum Name { A, B }, optionally with an encoding like enum Name: binary { A, B }
+    fn parse_enum(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<()> {
+        token_stream.error_if_empty(self.compilation, "identifier")?;
+
+        let name = token_stream.next().into_identifier_or_error(self.compilation)?;
 
-        self.project.composite_types.push(CompositeTypeSyntax { name: identifier, fields });
+        let mut encoding = EnumEncoding::default();
+        if token_stream.peek().is_some_and(|t| t.token_type().as_delimiter() == Some(&Delimiter::Colon)) {
+            token_stream.next();
+            token_stream.error_if_empty(self.compilation, "encoding")?;
+            let encoding_name = token_stream.next().into_identifier_or_error(self.compilation)?;
+            encoding = match encoding_name.value.as_str() {
+                "onehot" => EnumEncoding::OneHot,
+                "binary" => EnumEncoding::Binary,
+                _ => {
+                    self.compilation.add_error("Expected onehot or binary", encoding_name.location);
+                    return None;
+                }
+            };
+        }
 
+        token_stream.error_if_empty(self.compilation, "code block")?;
+        let body = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
+        let mut token_stream = TypeStream::from_iter(body.body.into_iter(), body.close_token.map(|s| s.code_location().to_owned()));
+
+        let mut variants = vec![];
+        while !token_stream.is_empty() {
+            variants.push(token_stream.next().into_identifier_or_error(self.compilation)?);
+            if token_stream.is_empty() {
+                break;
+            }
+            token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Comma)?;
+        }
 
+        self.project.enums.push(EnumSyntax { name, variants, encoding });
         Some(())
     }
     fn parse_super(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<()> {
@@ -113,13 +168,17 @@ impl<'a> Parser<'a> {
         let identifier_location = identifier.code_location().to_owned();
         let identifier = identifier.into_identifier_or_error(self.compilation)?;
         token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
-        let int = token_stream.next().into_integer_or_error(self.compilation)?;
+        let value = self.parse_expression(token_stream)?;
 
-        if self.project.supers.contains_key(&identifier.value) {
-            self.compilation.add_error("The name of this super is already in use", Some(identifier_location));
+        if let Some(previous) = self.project.supers.get(&identifier.value) {
+            let mut diagnostic = Diagnostic::new(DiagnosticType::Error, format!("The name of super \"{}\" is already in use", identifier.value), Some(identifier_location), DiagnosticPipelineLocation::Parsing);
+            if let Some(previous_location) = &previous.name.location {
+                diagnostic = diagnostic.with_visualisation(previous_location.clone(), "First defined here".to_string());
+            }
+            self.compilation.add_diagnostic(diagnostic);
             return None;
         }
-        self.project.supers.insert(identifier.value, int.value);
+        self.project.supers.insert(identifier.value.clone(), SuperSyntax { name: identifier, value });
 
         Some(())
     }
@@ -159,12 +218,29 @@ impl<'a> Parser<'a> {
             };
             token_stream.error_if_empty(self.compilation, "application")?;
 
-            let application = match self.parse_expression(&mut token_stream) {
-                Some(n) => n,
-                None => continue
+            //The arguments are usually given as a round block, but a single argument may also be given without one
+            let (location, arguments) = match token_stream.peek().and_then(|t| t.as_block_or_none().1).map(|b| b.brace_type) {
+                Some(Brace::Round) => {
+                    let (location, block) = token_stream.next().into_block_or_none();
+                    (Some(location), block.map_or(vec![], |b| self.parse_comma_separated_expressions(b)))
+                }
+                _ => match self.parse_expression(&mut token_stream) {
+                    Some(n) => (n.code_location(), vec![n]),
+                    None => continue
+                }
             };
 
-            self.project.solutions.insert(function_name.value.to_owned(), SubCallSyntax { location: SubLocation::Structure { collection: LocationValue::default(), sub: function_name }, application: Some(application) });
+            //The values of the witnesses follow the arguments, like test(true) witness { secret: false }
+            let mut witnesses = vec![];
+            if token_stream.peek().is_some_and(|t| matches!(t.token_type(), TokenBlockType::Token(TokenType::Keyword(Keyword::Witness)))) {
+                token_stream.next();
+                token_stream.error_if_empty(self.compilation, "code block")?;
+                let witness_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
+                witnesses = self.parse_field_assign(witness_block).unwrap_or_default();
+            }
+
+            let location = span(function_name.location.clone(), location);
+            self.project.solutions.push(SolutionSyntax { problem: function_name, arguments, witnesses, location });
             
             if token_stream.is_empty() {
                 break;
@@ -200,11 +276,14 @@ impl<'a> Parser<'a> {
 
         let name = token_stream.next().into_identifier_or_error(self.compilation)?;
 
+        let generics = self.parse_generic_parameters(token_stream)?;
+
         token_stream.error_if_empty(self.compilation, "args")?;
 
         let args = token_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
 
-        let args = self.parse_typed_identifiers(args)?;
+        let args: Vec<_> = self.parse_typed_identifiers(args, false)?.into_iter().map(|arg| TypedIdentifierSyntax { type_syntax: arg.type_syntax, name: arg.name }).collect();
+        self.sub_references = args.iter().filter(|arg| matches!(arg.type_syntax, TypeSyntax::Sub)).map(|arg| arg.name.value.clone()).collect();
 
         token_stream.error_if_empty(self.compilation, "code block")?;
 
@@ -217,10 +296,56 @@ impl<'a> Parser<'a> {
             token_stream.next();
             result = self.parse_expression(token_stream);
         }
-        Some(SubstructureSyntax { name, args, code, result })
+        Some(SubstructureSyntax { name, generics, args, code, result })
+    }
+
+    //Generic parameters of a declaration, like <N, M>. Declarations without them have no generics.
+    fn parse_generic_parameters(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<Vec<LocationValue<String>>> {
+        let mut generics = vec![];
+        if !token_stream.peek().is_some_and(|t| t.token_type().is_less_than()) {
+            return Some(generics);
+        }
+        token_stream.next();
+        loop {
+            token_stream.error_if_empty(self.compilation, "generic parameter")?;
+            generics.push(token_stream.next().into_identifier_or_error(self.compilation)?);
+            if self.parse_generic_separator(token_stream)? {
+                return Some(generics);
+            }
+        }
+    }
+
+    //Generic arguments of a type or sub call, like <32, N>. Operators need parentheses, like <(N + 1)>, so the closing > isn't read as a comparison
+    fn parse_generic_arguments(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<Vec<ExpressionSyntax>> {
+        let mut generics = vec![];
+        if !token_stream.peek().is_some_and(|t| t.token_type().is_less_than()) {
+            return Some(generics);
+        }
+        token_stream.next();
+        loop {
+            generics.push(self.parse_unary_expression(token_stream)?);
+            if self.parse_generic_separator(token_stream)? {
+                return Some(generics);
+            }
+        }
+    }
+
+    //Consumes a comma or the closing >. Returns true if the generics were closed.
+    fn parse_generic_separator(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<bool> {
+        token_stream.error_if_empty(self.compilation, ", or >")?;
+        let separator = token_stream.next();
+        match separator.token_type() {
+            TokenBlockType::Token(TokenType::Delimiter(Delimiter::Comma)) => Some(false),
+            TokenBlockType::Token(TokenType::Delimiter(Delimiter::GreaterThan)) => Some(true),
+            _ => {
+                self.compilation.add_error("Expected , or >", Some(separator.code_location().to_owned()));
+                None
+            }
+        }
     }
 
-    pub fn parse_typed_identifiers(&mut self, block: Block) -> Option<Vec<TypedIdentifierSyntax>> {
+    //Composite fields can additionally be marked optional with "?" or get a default value with "= value"
+    pub fn parse_typed_identifiers(&mut self, block: Block, allow_defaults: bool) -> Option<Vec<CompositeFieldSyntax>> {
         let mut enumeration = vec![];
         let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|f| f.code_location().to_owned()));
         while !token_stream.is_empty() {
@@ -236,8 +361,18 @@ impl<'a> Parser<'a> {
 
             let name = token_stream.next().into_identifier_or_error(self.compilation)?;
 
-            
-            enumeration.push(TypedIdentifierSyntax {type_syntax, name});
+            let optional = allow_defaults && token_stream.peek().is_some_and(|t| t.token_type().is_question_mark());
+            if optional {
+                token_stream.next();
+            }
+            let default = if allow_defaults && token_stream.peek().is_some_and(|t| t.token_type().is_equals()) {
+                token_stream.next();
+                Some(self.parse_expression(&mut token_stream)?)
+            } else {
+                None
+            };
+
+            enumeration.push(CompositeFieldSyntax { name, type_syntax, optional, default });
 
             if token_stream.is_empty() {
                 break;
@@ -254,7 +389,41 @@ impl<'a> Parser<'a> {
         let token = token_stream.next();
         match token.token_type() {
             TokenBlockType::Token(TokenType::Atom(Atom::Type(t))) => return Some(TypeSyntax::Atom(t.to_owned())),
-            TokenBlockType::Token(TokenType::Identifier(_)) => return Some(TypeSyntax::Composite { name: token.into_identifier_or_error(self.compilation)? }),
+            TokenBlockType::Token(TokenType::Identifier(name)) if name == "sup" => return Some(TypeSyntax::Super),
+            TokenBlockType::Token(TokenType::Keyword(Keyword::SubStructure)) => return Some(TypeSyntax::Sub),
+            TokenBlockType::Token(TokenType::Identifier(_)) => {
+                let name = token.into_identifier_or_error(self.compilation)?;
+                let generics = self.parse_generic_arguments(token_stream)?;
+                return Some(TypeSyntax::Composite { name, generics });
+            }
+            //Arrays, like [bool; 32] or [bool]
+            TokenBlockType::Block(b) if b.is_square() => {
+                let block = token.into_block_type_or_error(self.compilation, Brace::Square)?;
+                let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|f| f.code_location().to_owned()));
+                let t = self.parse_type(&mut token_stream)?;
+                let size = if token_stream.is_empty() {
+                    None
+                } else {
+                    token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Semicolon)?;
+                    token_stream.error_if_empty(self.compilation, "array size")?;
+                    Some(self.parse_expression(&mut token_stream)?)
+                };
+                token_stream.error_if_not_empty(self.compilation);
+                return Some(TypeSyntax::Array { t: t.into(), size });
+            }
+            //Tuples, like (bool, Int)
+            TokenBlockType::Block(b) if b.is_round() => {
+                let block = token.into_block_type_or_error(self.compilation, Brace::Round)?;
+                let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|f| f.code_location().to_owned()));
+                let mut elements = vec![];
+                while !token_stream.is_empty() {
+                    elements.push(self.parse_type(&mut token_stream)?);
+                    if !token_stream.is_empty() {
+                        token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Comma)?;
+                    }
+                }
+                return Some(TypeSyntax::Set { elements });
+            }
             _ => {
                 self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Error, format!("Expected type"), Some(token.code_location().to_owned()), DiagnosticPipelineLocation::Parsing));
                 return None;
@@ -274,7 +443,10 @@ impl<'a> Parser<'a> {
                 }
                 Some(TokenBlockType::Token(TokenType::Delimiter(Delimiter::Semicolon))) => { 
                     token_stream.next(); //Consume
-                    self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Warning, format!("Unneeded semicolon"), Some(token_stream.peek()?.code_location().to_owned()), DiagnosticPipelineLocation::Parsing));
+                    //A statement starting with a parenthesis would otherwise call a method at the end of the previous one
+                    if !token_stream.peek()?.token_type().is_round_block() {
+                        self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Warning, format!("Unneeded semicolon"), Some(token_stream.peek()?.code_location().to_owned()), DiagnosticPipelineLocation::Parsing));
+                    }
                 }
                 _ => {
                     if let Some(s) = self.parse_statement(&mut token_stream) {
@@ -297,13 +469,49 @@ impl<'a> Parser<'a> {
                 let iterator_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
                 let loop_code_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
 
+                let iterator_location = iterator_block.span.clone();
                 let mut iterator_stream = TypeStream::from_iter(iterator_block.body.into_iter(), iterator_block.close_token.map(|s| s.code_location().to_owned()));
-                let iterator_variable = iterator_stream.next().into_identifier_or_error(self.compilation)?;
+                iterator_stream.error_if_empty(self.compilation, "identifier")?;
+                //for ((i, x) in enumerate(a)) binds the index and the element
+                let (index_variable, iterator_variable) = match iterator_stream.peek().and_then(|t| t.as_block_or_none().1).map(|b| b.brace_type) {
+                    Some(Brace::Round) => {
+                        let pattern_block = iterator_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
+                        let mut pattern_stream = TypeStream::from_iter(pattern_block.body.into_iter(), pattern_block.close_token.map(|s| s.code_location().to_owned()));
+                        pattern_stream.error_if_empty(self.compilation, "identifier")?;
+                        let index_variable = pattern_stream.next().into_identifier_or_error(self.compilation)?;
+                        pattern_stream.error_if_empty(self.compilation, ",")?;
+                        pattern_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Comma)?;
+                        pattern_stream.error_if_empty(self.compilation, "identifier")?;
+                        let iterator_variable = pattern_stream.next().into_identifier_or_error(self.compilation)?;
+                        pattern_stream.error_if_not_empty(self.compilation)?;
+                        (Some(index_variable), iterator_variable)
+                    }
+                    _ => (None, iterator_stream.next().into_identifier_or_error(self.compilation)?)
+                };
+                iterator_stream.error_if_empty(self.compilation, "in")?;
                 iterator_stream.next().assert_is_keyword_or_error(self.compilation, Keyword::In)?;
-                let iterator_amount = self.parse_expression(&mut iterator_stream)?;
+                iterator_stream.error_if_empty(self.compilation, "iterator")?;
+
+                let enumerated = matches!(iterator_stream.peek().map(|t| t.token_type()), Some(TokenBlockType::Token(TokenType::Identifier(name))) if name == "enumerate");
+                let iterator = if enumerated {
+                    iterator_stream.next();
+                    iterator_stream.error_if_empty(self.compilation, "open paren")?;
+                    let enumerated_block = iterator_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
+                    let mut enumerated_stream = TypeStream::from_iter(enumerated_block.body.into_iter(), enumerated_block.close_token.map(|s| s.code_location().to_owned()));
+                    let iterator = self.parse_iterator(&mut enumerated_stream)?;
+                    enumerated_stream.error_if_not_empty(self.compilation)?;
+                    iterator
+                } else {
+                    self.parse_iterator(&mut iterator_stream)?
+                };
+                iterator_stream.error_if_not_empty(self.compilation)?;
+                if enumerated != index_variable.is_some() {
+                    self.compilation.add_error("Only enumerate binds an index and an element, like for ((i, x) in enumerate(a))", Some(iterator_location));
+                    return None;
+                }
 
                 let iterator_body  = self.parse_code_block(loop_code_block)?;
-                Some(CodeSyntax::For { iterator_variable, iterator_amount, iterator_body })
+                Some(CodeSyntax::For { iterator_variable, index_variable, iterator, iterator_body })
             }
 
             TokenBlockType::Token(TokenType::Keyword(Keyword::If)) => {
@@ -336,13 +544,25 @@ impl<'a> Parser<'a> {
             TokenBlockType::Token(TokenType::Keyword(Keyword::Let)) => {
                 token_stream.error_if_empty(self.compilation, "identifier")?;
 
-                let variable = token_stream.next().into_identifier_or_error(self.compilation)?;
+                let first = token_stream.next();
+                if first.token_type().as_block().is_some() || token_stream.peek().is_some_and(|s| s.token_type().is_curly_block()) {
+                    let pattern = self.parse_pattern(first, token_stream, true)?;
+                    token_stream.error_if_empty(self.compilation, "=")?;
+                    token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
+                    let value = self.parse_expression(token_stream)?;
+                    return Some(CodeSyntax::LetPattern { pattern, value });
+                }
+                let variable = first.into_identifier_or_error(self.compilation)?;
 
                 token_stream.error_if_empty(self.compilation, "=")?;
 
 
                 token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
                 let value = self.parse_expression(token_stream)?;
+                self.sub_references.retain(|name| *name != variable.value);
+                if matches!(value, ExpressionSyntax::SubReference { .. }) {
+                    self.sub_references.push(variable.value.clone());
+                }
                 return Some(CodeSyntax::Let { variable, value });
             }
             TokenBlockType::Token(TokenType::Keyword(Keyword::Force)) => {
@@ -355,19 +575,63 @@ impl<'a> Parser<'a> {
                 let type_syntax = self.parse_type(token_stream)?;
                 return Some(CodeSyntax::Force { value, type_syntax }); 
             }
+            TokenBlockType::Token(TokenType::Keyword(Keyword::Witness)) => {
+                let type_syntax = self.parse_type(token_stream)?;
+                token_stream.error_if_empty(self.compilation, "colon")?;
+                token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Colon)?;
+                token_stream.error_if_empty(self.compilation, "identifier")?;
+                let name = token_stream.next().into_identifier_or_error(self.compilation)?;
+                return Some(CodeSyntax::Witness { declaration: TypedIdentifierSyntax { name, type_syntax } })
+            }
             TokenBlockType::Token(TokenType::Keyword(Keyword::Output)) => {
                 let expression = self.parse_expression(token_stream)?;
                 return Some(CodeSyntax::Output { expression })
             }
+            TokenBlockType::Token(TokenType::Keyword(Keyword::StaticAssert)) => {
+                let location = statement.code_location().to_owned();
+                token_stream.error_if_empty(self.compilation, "open paren")?;
+                let arguments_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
+                let mut arguments = self.parse_comma_separated_expressions(arguments_block);
+                if arguments.is_empty() || arguments.len() > 2 {
+                    self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Error, "Expected a condition and an optional message".to_string(), Some(location), DiagnosticPipelineLocation::Parsing));
+                    return None;
+                }
+                let message = if arguments.len() == 2 { arguments.pop() } else { None };
+                let condition = arguments.pop()?;
+                return Some(CodeSyntax::StaticAssert { condition, message, location: Some(location) })
+            }
+            TokenBlockType::Token(TokenType::Keyword(Keyword::CompileError)) => {
+                let location = statement.code_location().to_owned();
+                let message = self.parse_expression(token_stream)?;
+                return Some(CodeSyntax::CompileError { message, location: Some(location) })
+            }
+            TokenBlockType::Token(TokenType::Keyword(Keyword::Match)) => {
+                token_stream.error_if_empty(self.compilation, "open paren")?;
+                let value_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
+                let mut value_stream = TypeStream::from_iter(value_block.body.into_iter(), value_block.close_token.map(|s| s.code_location().to_owned()));
+                let value = self.parse_expression(&mut value_stream)?;
+                value_stream.error_if_not_empty(self.compilation);
+
+                token_stream.error_if_empty(self.compilation, "code block")?;
+                let arms_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
+                let arms = self.parse_match_arms(arms_block)?;
+                return Some(CodeSyntax::Match { value, arms })
+            }
+            TokenBlockType::Token(TokenType::Keyword(Keyword::Return)) => {
+                let value = self.parse_expression(token_stream)?;
+                return Some(CodeSyntax::Return { value })
+            }
             TokenBlockType::Token(TokenType::Identifier(structure)) if token_stream.peek().is_some_and(|s| s.token_type().is_double_colon()) => {
                 token_stream.next();
                 token_stream.error_if_empty(self.compilation, "identifier")?;
 
                 let sub = token_stream.next().into_identifier_or_error(self.compilation)?;
+                let generics = self.parse_generic_arguments(token_stream)?;
 
-                let application = self.parse_expression(token_stream)?;
+                let application = self.parse_primary_expression(token_stream)?;
                 let syntax = SubCallSyntax {
                     application: Some(application),
+                    generics,
                     location: SubLocation::Structure {
                         collection: LocationValue::new(Some(statement.code_location().to_owned()), structure.to_owned()),
                         sub
@@ -376,9 +640,49 @@ impl<'a> Parser<'a> {
                 return Some(CodeSyntax::Sub(syntax.into()))
 
             }
+            TokenBlockType::Token(TokenType::Identifier(name)) if token_stream.peek().is_some_and(|t| self.is_call(name, t)) => {
+                let name = statement.into_identifier_or_error(self.compilation)?;
+                let application = self.parse_primary_expression(token_stream)?;
+                let syntax = SubCallSyntax {
+                    application: Some(application),
+                    generics: vec![],
+                    location: SubLocation::Reference(name)
+                };
+                return Some(CodeSyntax::Sub(syntax))
+            }
+            TokenBlockType::Block(Brace::Round) => {
+                let pattern = self.parse_pattern(statement, token_stream, false)?;
+                token_stream.error_if_empty(self.compilation, "=")?;
+                token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
+                let value = self.parse_expression(token_stream)?;
+                return Some(CodeSyntax::ReassignPattern { pattern, value });
+            }
+            TokenBlockType::Token(TokenType::Identifier(_)) if token_stream.peek().is_some_and(|s| s.token_type().is_curly_block()) => {
+                let pattern = self.parse_pattern(statement, token_stream, false)?;
+                token_stream.error_if_empty(self.compilation, "=")?;
+                token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
+                let value = self.parse_expression(token_stream)?;
+                return Some(CodeSyntax::ReassignPattern { pattern, value });
+            }
             TokenBlockType::Token(TokenType::Identifier(_)) => {
                 let base = statement.into_identifier_or_error(self.compilation)?;
                 let variable = self.parse_access(token_stream, base)?;
+                if !variable.is_place() {
+                    let assigned = token_stream.peek().is_some_and(|t| t.token_type().is_equals());
+                    match variable {
+                        //A method call used as a statement, like a.output_as_string()
+                        ExpressionSyntax::Sub(sub_call) if !assigned => return Some(CodeSyntax::Sub(*sub_call)),
+                        variable => {
+                            self.compilation.add_error("Only variables and their fields can be assigned to", variable.code_location());
+                            //The assigned value is skipped, so parsing can continue with the next statement
+                            if assigned {
+                                token_stream.next();
+                                self.parse_expression(token_stream);
+                            }
+                            return None;
+                        }
+                    }
+                }
                 token_stream.error_if_empty(self.compilation, "=")?;
                 token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Equals);
                 let value = self.parse_expression(token_stream)?;
@@ -391,7 +695,65 @@ impl<'a> Parser<'a> {
         }
     }
 
+    //Either a single value, like n or an array, or a range like a..b step s
+    fn parse_iterator(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<IteratorSyntax> {
+        let start = self.parse_expression(token_stream)?;
+        if !token_stream.peek().is_some_and(|t| t.token_type() == TokenBlockType::Token(&TokenType::Delimiter(Delimiter::DoublePeriod))) {
+            return Some(IteratorSyntax::Value(start));
+        }
+        token_stream.next();
+        let end = self.parse_expression(token_stream)?;
+        let step = if matches!(token_stream.peek().map(|t| t.token_type()), Some(TokenBlockType::Token(TokenType::Identifier(name))) if name == "step") {
+            token_stream.next();
+            Some(self.parse_expression(token_stream)?.into())
+        } else {
+            None
+        };
+        Some(IteratorSyntax::Range { start: start.into(), end: end.into(), step })
+    }
+
     pub fn parse_expression(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<ExpressionSyntax> {
+        self.parse_binary_expression(token_stream, 0)
+    }
+
+    //Precedence climbing, only operators binding at least as tight as min_precedence are consumed
+    fn parse_binary_expression(&mut self, token_stream: &mut TypeStream<TokenBlock>, min_precedence: u8) -> Option<ExpressionSyntax> {
+        let mut left = self.parse_unary_expression(token_stream)?;
+
+        while let Some(operator) = token_stream.peek().and_then(|t| InfixOperator::from_token(t.token_type())) {
+            if operator.precedence() < min_precedence {
+                break;
+            }
+            let operator_location = token_stream.next().code_location().to_owned();
+            //All operators are left associative
+            let right = self.parse_binary_expression(token_stream, operator.precedence() + 1)?;
+            left = operator.desugar(left, right, operator_location);
+        }
+        Some(left)
+    }
+
+    fn parse_unary_expression(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<ExpressionSyntax> {
+        token_stream.error_if_empty(self.compilation, "expression")?;
+
+        if token_stream.peek().is_some_and(|t| t.token_type().as_delimiter() == Some(&Delimiter::ExclamationMark)) {
+            let location = token_stream.next().code_location().to_owned();
+            let value = self.parse_unary_expression(token_stream)?;
+            let location = span(Some(location), value.code_location());
+            return Some(atom_call(AtomSub::Not, value, location));
+        }
+        //Parentheses around a single expression only group it, with commas they create a tuple
+        let is_comma = |t: &TokenBlock| t.token_type() == TokenBlockType::Token(&TokenType::Delimiter(Delimiter::Comma));
+        if token_stream.peek().and_then(|t| t.as_block_or_none().1).is_some_and(|b| b.brace_type == Brace::Round && !b.body.is_empty() && !b.body.iter().any(is_comma)) {
+            let block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Round)?;
+            let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|s| s.code_location().to_owned()));
+            let expression = self.parse_expression(&mut token_stream)?;
+            token_stream.error_if_not_empty(self.compilation);
+            return Some(expression);
+        }
+        self.parse_primary_expression(token_stream)
+    }
+
+    fn parse_primary_expression(&mut self, token_stream: &mut TypeStream<TokenBlock>) -> Option<ExpressionSyntax> {
 
         token_stream.error_if_empty(self.compilation, "expression")?;
 
@@ -415,10 +777,12 @@ impl<'a> Parser<'a> {
                 token_stream.error_if_empty(self.compilation, "identifier")?;
 
                 let sub = token_stream.next().into_identifier_or_error(self.compilation)?;
+                let generics = self.parse_generic_arguments(token_stream)?;
 
-                let application = self.parse_expression(token_stream)?;
+                let application = self.parse_primary_expression(token_stream)?;
                 let syntax = SubCallSyntax {
                     application: Some(application),
+                    generics,
                     location: SubLocation::Structure {
                         collection: structure.to_owned(),
                         sub
@@ -427,6 +791,16 @@ impl<'a> Parser<'a> {
                 return Some(ExpressionSyntax::Sub(syntax.into()))
 
             }
+            //References to a sub are marked with the sub keyword, like sub bool::and
+            TokenBlockType::Token(TokenType::Keyword(Keyword::SubStructure)) => {
+                token_stream.error_if_empty(self.compilation, "identifier")?;
+                let collection = token_stream.next().into_identifier_or_error(self.compilation)?;
+                token_stream.error_if_empty(self.compilation, "::")?;
+                token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::DoubleColon)?;
+                token_stream.error_if_empty(self.compilation, "identifier")?;
+                let sub = token_stream.next().into_identifier_or_error(self.compilation)?;
+                Some(ExpressionSyntax::SubReference { collection, sub })
+            }
             TokenBlockType::Token(TokenType::Keyword(Keyword::Super)) if token_stream.peek().is_some_and(|s| s.token_type().is_double_colon()) => {
                 
                 token_stream.next();
@@ -434,9 +808,10 @@ impl<'a> Parser<'a> {
 
                 let sub = token_stream.next().into_identifier_or_error(self.compilation)?;
 
-                let application = self.parse_expression(token_stream)?;
+                let application = self.parse_primary_expression(token_stream)?;
                 let syntax = SubCallSyntax {
                     application: Some(application),
+                    generics: vec![],
                     location: SubLocation::Super(sub)
                 };
                 return Some(ExpressionSyntax::Sub(syntax.into()))
@@ -451,6 +826,16 @@ impl<'a> Parser<'a> {
                 return Some(ExpressionSyntax::CompositeConstructor { type_name: type_name.to_owned(), field_assign })
 
             }
+            TokenBlockType::Token(TokenType::Identifier(name)) if token_stream.peek().is_some_and(|t| self.is_call(name, t)) => {
+                let name = node_value_token.into_identifier_or_error(self.compilation)?;
+                let application = self.parse_primary_expression(token_stream)?;
+                let syntax = SubCallSyntax {
+                    application: Some(application),
+                    generics: vec![],
+                    location: SubLocation::Reference(name)
+                };
+                return Some(ExpressionSyntax::Sub(syntax.into()))
+            }
             TokenBlockType::Token(TokenType::Identifier(_)) => {
                 let base = node_value_token.into_identifier_or_error(self.compilation)?;
                 return self.parse_access(token_stream, base);
@@ -458,9 +843,10 @@ impl<'a> Parser<'a> {
 
             TokenBlockType::Token(TokenType::Atom(Atom::Sub(_))) => {
                 let sub = node_value_token.into_atom_sub_or_error(self.compilation)?;
-                let application = self.parse_expression(token_stream)?;
+                let application = self.parse_unary_expression(token_stream)?;
                 let syntax = SubCallSyntax {
                     application: Some(application),
+                    generics: vec![],
                     location: SubLocation::Atom(sub.to_owned())
                 };
                 return Some(ExpressionSyntax::Sub(syntax.into()))
@@ -497,6 +883,103 @@ impl<'a> Parser<'a> {
 
         }        
     }
+    //Arms like A | B => { ... }, the else arm has to be the last one
+    fn parse_match_arms(&mut self, block: Block) -> Option<Vec<MatchArmSyntax>> {
+        let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|s| s.code_location().to_owned()));
+        let mut arms: Vec<MatchArmSyntax> = vec![];
+
+        while !token_stream.is_empty() {
+            let first = token_stream.next();
+            if arms.last().is_some_and(|arm| arm.variants.is_none()) {
+                self.compilation.add_error("The else arm has to be the last arm", Some(first.code_location().to_owned()));
+                return None;
+            }
+            let variants = if first.token_type() == TokenBlockType::Token(&TokenType::Keyword(Keyword::Else)) {
+                None
+            } else {
+                let mut variants = vec![first.into_identifier_or_error(self.compilation)?];
+                while token_stream.peek().is_some_and(|t| t.token_type().as_delimiter() == Some(&Delimiter::Pipe)) {
+                    token_stream.next();
+                    token_stream.error_if_empty(self.compilation, "identifier")?;
+                    variants.push(token_stream.next().into_identifier_or_error(self.compilation)?);
+                }
+                Some(variants)
+            };
+            token_stream.error_if_empty(self.compilation, "=>")?;
+            token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::ThickArrowRight)?;
+            token_stream.error_if_empty(self.compilation, "code block")?;
+            let code_block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
+            let code = self.parse_code_block(code_block).unwrap_or_default();
+            arms.push(MatchArmSyntax { variants, code });
+
+            if token_stream.peek().is_some_and(|t| t.token_type().as_delimiter() == Some(&Delimiter::Comma)) {
+                token_stream.next();
+            }
+        }
+        Some(arms)
+    }
+
+    //Parses the pattern starting with the already consumed first token. Let patterns bind variable names, reassignments accept any assignable expression
+    fn parse_pattern(&mut self, first: TokenBlock, token_stream: &mut TypeStream<TokenBlock>, is_let: bool) -> Option<PatternSyntax> {
+        match first.token_type() {
+            TokenBlockType::Block(Brace::Round) => {
+                let block = first.into_block_type_or_error(self.compilation, Brace::Round)?;
+                let location = Some(block.span.to_owned());
+                let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|s| s.code_location().to_owned()));
+                let mut elements = vec![];
+                while !token_stream.is_empty() {
+                    let first = token_stream.next();
+                    elements.push(self.parse_pattern(first, &mut token_stream, is_let)?);
+                    if token_stream.is_empty() {
+                        break;
+                    }
+                    token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Comma)?;
+                }
+                Some(PatternSyntax::Tuple { elements, location })
+            }
+            TokenBlockType::Token(TokenType::Identifier(_)) if token_stream.peek().is_some_and(|s| s.token_type().is_curly_block()) => {
+                let type_name = first.into_identifier_or_error(self.compilation)?;
+                let block = token_stream.next().into_block_type_or_error(self.compilation, Brace::Curly)?;
+                let location = type_name.location.as_ref().map(|l| l.to(&block.span));
+                let mut token_stream = TypeStream::from_iter(block.body.into_iter(), block.close_token.map(|s| s.code_location().to_owned()));
+                let mut fields = vec![];
+                while !token_stream.is_empty() {
+                    let field = token_stream.next().into_identifier_or_error(self.compilation)?;
+                    let pattern = if token_stream.peek().is_some_and(|s| s.token_type().as_delimiter() == Some(&Delimiter::Colon)) {
+                        token_stream.next();
+                        token_stream.error_if_empty(self.compilation, "pattern")?;
+                        let first = token_stream.next();
+                        self.parse_pattern(first, &mut token_stream, is_let)?
+                    } else {
+                        PatternSyntax::Single(ExpressionSyntax::Variable(field.clone()))
+                    };
+                    fields.push((field, pattern));
+                    if token_stream.is_empty() {
+                        break;
+                    }
+                    token_stream.next().assert_is_delimiter_or_error(self.compilation, Delimiter::Comma)?;
+                }
+                Some(PatternSyntax::Composite { type_name, fields, location })
+            }
+            TokenBlockType::Token(TokenType::Identifier(_)) => {
+                let base = first.into_identifier_or_error(self.compilation)?;
+                if is_let {
+                    return Some(PatternSyntax::Single(ExpressionSyntax::Variable(base)));
+                }
+                Some(PatternSyntax::Single(self.parse_access(token_stream, base)?))
+            }
+            _ => {
+                self.compilation.add_error("Expected pattern", Some(first.code_location().to_owned()));
+                None
+            }
+        }
+    }
+
+    //A variable holding a sub reference followed by a round block is a call, like f(a, b). After any other variable, the block starts the next statement
+    fn is_call(&self, name: &str, next: &TokenBlock) -> bool {
+        next.token_type().is_round_block() && self.sub_references.iter().any(|reference| reference == name)
+    }
+
     pub fn parse_access(&mut self, token_stream: &mut TypeStream<TokenBlock>, base: LocationValue<String>) -> Option<ExpressionSyntax> {
         let mut chain = ExpressionSyntax::Variable(base);
 
@@ -513,7 +996,24 @@ impl<'a> Parser<'a> {
                         },
                         TokenBlockType::Token(TokenType::Identifier(_)) => {
                             let identifier = access_token.into_identifier_or_error(self.compilation)?;
-                            ExpressionSyntax::Access { base: chain.into(), field: identifier }
+                            //Parentheses after a field name make it a method call, like a.add(b)
+                            if token_stream.peek().is_some_and(|t| t.token_type().is_round_block()) {
+                                let arguments = match self.parse_primary_expression(token_stream)? {
+                                    ExpressionSyntax::Tuple(mut items) => {
+                                        items.insert(0, chain);
+                                        items
+                                    }
+                                    argument => vec![chain, argument]
+                                };
+                                let syntax = SubCallSyntax {
+                                    application: Some(ExpressionSyntax::Tuple(arguments)),
+                                    generics: vec![],
+                                    location: SubLocation::Method(identifier)
+                                };
+                                ExpressionSyntax::Sub(syntax.into())
+                            } else {
+                                ExpressionSyntax::Access { base: chain.into(), field: identifier }
+                            }
                         }
                         _ => {
                             self.compilation.add_error("Expected integer or identifier", Some(access_token.code_location().to_owned()));
@@ -608,3 +1108,250 @@ impl<'a> Parser<'a> {
     }
 }
 
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InfixOperator {
+    Or,
+    Xor,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Lte,
+    Gte,
+    Add,
+    Sb,
+    Mul,
+    Div,
+    Mod,
+}
+impl InfixOperator {
+    fn from_token(token_type: TokenBlockType) -> Option<Self> {
+        let TokenBlockType::Token(TokenType::Delimiter(delimiter)) = token_type else {
+            return None;
+        };
+        match delimiter {
+            Delimiter::Pipe => Some(Self::Or),
+            Delimiter::Caret => Some(Self::Xor),
+            Delimiter::Ampersand => Some(Self::And),
+            Delimiter::DoubleEquals => Some(Self::Eq),
+            Delimiter::NotEquals => Some(Self::Ne),
+            Delimiter::LessThan => Some(Self::Lt),
+            Delimiter::GreaterThan => Some(Self::Gt),
+            Delimiter::LessThanOrEquals => Some(Self::Lte),
+            Delimiter::GreaterThanOrEquals => Some(Self::Gte),
+            Delimiter::Plus => Some(Self::Add),
+            Delimiter::Minus => Some(Self::Sb),
+            Delimiter::Star => Some(Self::Mul),
+            Delimiter::Slash => Some(Self::Div),
+            Delimiter::Percent => Some(Self::Mod),
+            _ => None
+        }
+    }
+
+    //Higher binds tighter
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Or => 0,
+            Self::Xor => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Gt | Self::Lte | Self::Gte => 3,
+            Self::Add | Self::Sb => 4,
+            Self::Mul | Self::Div | Self::Mod => 5,
+        }
+    }
+
+    //| and & become atom subs directly, ==, != and ^ are equality expressions and the arithmetic operators are super builtins
+    fn desugar(self, left: ExpressionSyntax, right: ExpressionSyntax, operator_location: CodeLocation) -> ExpressionSyntax {
+        let location = span(left.code_location(), right.code_location()).or(Some(operator_location.clone()));
+        let name = match self {
+            Self::Or => return atom_call(AtomSub::Or, ExpressionSyntax::Tuple(vec![left, right]), location),
+            Self::And => {
+                //a & b = !(!a | !b)
+                let (left_location, right_location) = (left.code_location(), right.code_location());
+                let negated = ExpressionSyntax::Tuple(vec![atom_call(AtomSub::Not, left, left_location), atom_call(AtomSub::Not, right, right_location)]);
+                return atom_call(AtomSub::Not, atom_call(AtomSub::Or, negated, location.clone()), location);
+            }
+            Self::Eq => return equality(EqualityOperator::Eq, left, right, operator_location),
+            Self::Ne => return equality(EqualityOperator::Ne, left, right, operator_location),
+            Self::Xor => return equality(EqualityOperator::Xor, left, right, operator_location),
+            Self::Lt => "lt",
+            Self::Gt => "gt",
+            Self::Lte => "lte",
+            Self::Gte => "gte",
+            Self::Add => "add",
+            Self::Sb => "sb",
+            Self::Mul => "mul",
+            Self::Div => "div",
+            Self::Mod => "mod",
+        };
+        ExpressionSyntax::Sub(SubCallSyntax {
+            application: Some(ExpressionSyntax::Tuple(vec![left, right])),
+            generics: vec![],
+            location: SubLocation::Super(LocationValue::new(location, name.to_string()))
+        }.into())
+    }
+}
+
+fn equality(operator: EqualityOperator, left: ExpressionSyntax, right: ExpressionSyntax, location: CodeLocation) -> ExpressionSyntax {
+    ExpressionSyntax::Equality {
+        operator: LocationValue::new(Some(location), operator),
+        left: left.into(),
+        right: right.into()
+    }
+}
+
+fn atom_call(sub: AtomSub, application: ExpressionSyntax, location: Option<CodeLocation>) -> ExpressionSyntax {
+    ExpressionSyntax::Sub(SubCallSyntax {
+        application: Some(application),
+        generics: vec![],
+        location: SubLocation::Atom(LocationValue::new(location, sub))
+    }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::{compile_source, diagnostic::DiagnosticType};
+
+    #[test]
+    fn infix_operators() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: b) {
+                    output (a & b, a | !b, a ^ b, a == b, (a | b) & !b)
+                    output (2 + 3 * 4, (2 + 3) * 4, 9 - 4 - 1, 7 % 4 < 3)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(False, True, True, False, True, )".to_string(), "(14, 20, 4, false, )".to_string()]);
+    }
+
+    #[test]
+    fn equality_operators() {
+        let output = compile_source(r#"
+            enum Color { Red, Green }
+            problem {
+                sub test(bool: a, bool: b, Color: c) {
+                    output (a != b, a == true, true ^ true, c == Color.Green, c != Color.Red)
+                    output (3 == 3, 3 != 3, 2 + 2 == 4)
+                }
+            }
+            solution {
+                test(false, true, Color.Green)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(True, False, False, True, True, )".to_string(), "(true, false, true, )".to_string()]);
+
+        //^ is only defined on booleans, and the super builtins only compare integers
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a) {
+                    output 3 ^ 4
+                    output super::eq(a, true)
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).map(|d| d.description.as_str()).collect();
+        assert_eq!(errors, vec!["^ is only defined on booleans, found sup", "Mismatched types: expected sup, found bool", "Mismatched types: expected sup, found bool"]);
+    }
+
+    #[test]
+    fn sub_references() {
+        let output = compile_source(r#"
+            import std::bool
+            import std::array
+            collection ops {
+                sub twice(bool: x, sub: f) {
+                    let y = f(x)
+                } = f(y)
+                sub flip(bool: x) {} = not x
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    let f = sub ops::flip
+                    output ops::twice(a, f)
+                    output array::zip_with([a, b], [true, true], sub bool::and)
+                    output array::fold([a, b], false, sub bool::xor)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["True".to_string(), "[True, False, ]".to_string(), "True".to_string()]);
+
+        //Without the marker, a sub is called with whatever follows it
+        let output = compile_source(r#"
+            import std::bool
+            collection c {
+                sub flip(bool: x) {} = not x
+                sub both(bool: x, bool: y) {} = bool::and(x, y)
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    let x = c::flip a
+                    let y = c::both (a, b)
+                    output (x, y)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(False, False, )".to_string()]);
+
+        //Only variables holding a reference are called, a round block after any other variable starts the next statement
+        let output = compile_source(r#"
+            collection c {
+                sub flip(bool: x) {} = not x
+                sub apply(sub: f, bool: x) {
+                    let y = f (x)
+                } = y
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    let g = sub c::flip
+                    let x = a
+                    (a, b) = (b, x)
+                    let p = (a, b)
+                    let y = p.0;
+                    (a, b) = (b, y)
+                    output (g (a), c::apply(g, b))
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(False, True, )".to_string()]);
+    }
+
+    #[test]
+    fn methods() {
+        let output = compile_source(r#"
+            composite Pair {
+                bool: a,
+                bool: b
+            }
+            impl Pair {
+                sub swap(Pair: p) {} = Pair { a: p.b, b: p.a }
+                sub first(Pair: p) {} = p.a
+            }
+            problem {
+                sub test(bool: x, bool: y) {
+                    let p = Pair { a: x, b: y }
+                    output p.swap().first()
+                    output p.swap ().swap().a
+                    output Pair::first(p)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["False".to_string(), "True".to_string(), "True".to_string()]);
+    }
+}
@@ -1,14 +1,60 @@
 use std::collections::HashMap;
 
-pub struct Scope<'a, T> {
-    vars: Vec<HashMap<&'a str, T>>
+///A stack of variable frames. Lookups search from the innermost frame outwards, new variables are always defined in the innermost frame.
+#[derive(Debug, Clone)]
+pub struct Scope<T> {
+    frames: Vec<HashMap<String, T>>
 }
 
-impl<T> Scope<'_, T> {
-    pub fn get_var_mut(&mut self, name: &str) -> Option<&mut T> {
-        for v in self.vars.iter().rev() {
-            //if let Some()
-        }
-        todo!()
+impl<T> Default for Scope<T> {
+    fn default() -> Self {
+        Self { frames: vec![HashMap::new()] }
     }
-}
\ No newline at end of file
+}
+
+impl<T> Scope<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    pub fn pop(&mut self) {
+        assert!(self.frames.len() > 1, "Tried to pop the outermost scope");
+        self.frames.pop();
+    }
+
+    ///Defines a variable in the innermost frame, shadowing variables of the same name in outer frames.
+    pub fn insert(&mut self, name: String, value: T) {
+        self.frames.last_mut().expect("Scope should have at least one frame").insert(name, value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut T> {
+        self.frames.iter_mut().rev().find_map(|frame| frame.get_mut(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scope;
+
+    #[test]
+    fn inner_frames_shadow_and_drop() {
+        let mut scope = Scope::new();
+        scope.insert("a".to_string(), 1);
+        scope.push();
+        scope.insert("a".to_string(), 2);
+        scope.insert("b".to_string(), 3);
+        *scope.get_mut("a").unwrap() += 10;
+        assert_eq!(scope.get("a"), Some(&12));
+        scope.pop();
+        assert_eq!(scope.get("a"), Some(&1));
+        assert_eq!(scope.get("b"), None);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::compiler::export::ExportFormat;
+
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub color: bool,
@@ -5,14 +7,21 @@ pub struct Settings {
     pub heavy_optimization: bool,
     pub output_code_logs: bool,
     pub print_debug_logs: bool,
+    pub output_progress: bool,
     pub output_diagnostics: bool,
     pub base_path: Option<String>,
     pub output_directory: Option<String>,
+    pub output_name: String,
     pub ignore_errors: bool,
+    pub solve: bool,
+    pub emit: Vec<ExportFormat>,
+    pub decode_model: Option<String>,
+    //Values that replace the ones of super constants in the project
+    pub super_overrides: Vec<(String, String)>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
-        Self { color: true, optimize: false, output_code_logs: true, print_debug_logs: false, output_diagnostics: true, output_directory: None, ignore_errors: false, heavy_optimization: false, base_path: Some("./".into()) }
+        Self { color: true, optimize: false, output_code_logs: true, print_debug_logs: false, output_progress: true, output_diagnostics: true, output_directory: None, output_name: "compiled".into(), ignore_errors: false, heavy_optimization: false, base_path: Some("./".into()), solve: false, emit: vec![ExportFormat::Csv], decode_model: None, super_overrides: vec![] }
     }
 }
@@ -0,0 +1,253 @@
+/*
+Verifies and searches 3-colorings of a compiled graph.
+The search is a backtracking solver, which picks the next node using DSATUR ordering (fewest remaining colors, highest degree first)
+and propagates every decision over the label whitelists of the neighbouring nodes.
+*/
+
+use std::collections::HashSet;
+
+use crate::compiler::atom_tree_to_graph::{Label, Node};
+
+const COLORS: [Label; 3] = [Label::True, Label::False, Label::Neutral];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColoringError {
+    //The node doesn't have a label assigned to it
+    Unlabelled(usize),
+    //The label of the node isn't part of its whitelist
+    NotWhitelisted(usize, Label),
+    //Two connected nodes share the same label
+    Conflict(usize, usize),
+}
+
+impl ColoringError {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Unlabelled(node) => format!("Node {node} doesn't have a label"),
+            Self::NotWhitelisted(node, label) => format!("Node {node} is labelled {:?}, which its restrictions don't allow", label),
+            Self::Conflict(a, b) => format!("Connected nodes {a} and {b} share the same label"),
+        }
+    }
+}
+
+///Checks whether the labels of the nodes form a valid 3-coloring. Returns every violation found.
+pub fn verify(nodes: &[Node]) -> Vec<ColoringError> {
+    let labels: Vec<Label> = nodes.iter().map(|n| n.label).collect();
+    verify_labels(nodes, &labels)
+}
+
+///Checks whether the given labels form a valid 3-coloring of the graph. Returns every violation found.
+pub fn verify_labels(nodes: &[Node], labels: &[Label]) -> Vec<ColoringError> {
+    let mut errors = vec![];
+    for (i, node) in nodes.iter().enumerate() {
+        let label = labels.get(i).copied().unwrap_or(Label::Null);
+        if label == Label::Null {
+            errors.push(ColoringError::Unlabelled(i));
+            continue;
+        }
+        if !node.label_whitelist.contains(&label) {
+            errors.push(ColoringError::NotWhitelisted(i, label));
+        }
+        for &connection in &node.connections {
+            //Every edge is stored on both nodes, so we only report it once.
+            if connection > i && labels.get(connection) == Some(&label) {
+                errors.push(ColoringError::Conflict(i, connection));
+            }
+        }
+    }
+    errors
+}
+
+///Searches for a valid 3-coloring of the graph. Labels that are already known are kept, only `Label::Null` nodes are searched.
+///Returns None if no coloring exists.
+pub fn solve(nodes: &[Node]) -> Option<Vec<Label>> {
+    Solver::new(nodes).solve()
+}
+
+struct Decision {
+    node: usize,
+    //Colors that haven't been tried yet
+    remaining: u8,
+    trail_len: usize,
+}
+
+struct Solver {
+    adjacency: Vec<Vec<usize>>,
+    domains: Vec<u8>,
+    //Every domain change, so it can be undone when backtracking
+    trail: Vec<(usize, u8)>,
+}
+
+impl Solver {
+    fn new(nodes: &[Node]) -> Self {
+        let adjacency = nodes.iter().enumerate().map(|(i, node)| {
+            let set: HashSet<usize> = node.connections.iter().copied().filter(|&c| c != i).collect();
+            set.into_iter().collect()
+        }).collect();
+
+        let domains = nodes.iter().map(|node| {
+            let mut domain = 0;
+            for color in COLORS {
+                if node.label_whitelist.contains(&color) {
+                    domain |= color_bit(color);
+                }
+            }
+            if node.label != Label::Null {
+                domain &= color_bit(node.label);
+            }
+            domain
+        }).collect();
+
+        Self {
+            adjacency,
+            domains,
+            trail: vec![],
+        }
+    }
+
+    fn solve(mut self) -> Option<Vec<Label>> {
+        if self.domains.contains(&0) {
+            return None;
+        }
+        let fixed: Vec<usize> = (0..self.domains.len()).filter(|&i| self.domains[i].count_ones() == 1).collect();
+        if !self.propagate(fixed) {
+            return None;
+        }
+
+        let mut decisions: Vec<Decision> = vec![];
+        while let Some(node) = self.select_node() {
+            decisions.push(Decision { node, remaining: self.domains[node], trail_len: self.trail.len() });
+
+            //Try the remaining colors of the latest decision, backtracking to earlier decisions when they run out.
+            loop {
+                let decision = decisions.last_mut()?;
+                if decision.remaining == 0 {
+                    let decision = decisions.pop()?;
+                    self.undo(decision.trail_len);
+                    continue;
+                }
+                let color = decision.remaining & decision.remaining.wrapping_neg();
+                decision.remaining &= !color;
+                let (node, trail_len) = (decision.node, decision.trail_len);
+
+                self.undo(trail_len);
+                self.set_domain(node, color);
+                if self.propagate(vec![node]) {
+                    break;
+                }
+            }
+        }
+
+        Some(self.domains.iter().map(|&d| bit_color(d)).collect())
+    }
+
+    //DSATUR: the unassigned node with the fewest remaining colors, ties broken by the highest degree.
+    fn select_node(&self) -> Option<usize> {
+        let mut best: Option<(usize, u32, usize)> = None;
+        for (i, &domain) in self.domains.iter().enumerate() {
+            let options = domain.count_ones();
+            if options <= 1 {
+                continue;
+            }
+            let degree = self.adjacency[i].len();
+            let better = match best {
+                Some((_, best_options, best_degree)) => options < best_options || (options == best_options && degree > best_degree),
+                None => true,
+            };
+            if better {
+                best = Some((i, options, degree));
+            }
+        }
+        best.map(|(i, _, _)| i)
+    }
+
+    //Removes the colors of assigned nodes from their neighbours. Returns false if a node runs out of colors.
+    fn propagate(&mut self, mut queue: Vec<usize>) -> bool {
+        while let Some(node) = queue.pop() {
+            let color = self.domains[node];
+            for i in 0..self.adjacency[node].len() {
+                let neighbour = self.adjacency[node][i];
+                let domain = self.domains[neighbour];
+                if domain & color == 0 {
+                    continue;
+                }
+                let new_domain = domain & !color;
+                if new_domain == 0 {
+                    return false;
+                }
+                self.set_domain(neighbour, new_domain);
+                if new_domain.count_ones() == 1 {
+                    queue.push(neighbour);
+                }
+            }
+        }
+        true
+    }
+
+    fn set_domain(&mut self, node: usize, domain: u8) {
+        self.trail.push((node, self.domains[node]));
+        self.domains[node] = domain;
+    }
+
+    fn undo(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            let (node, domain) = self.trail.pop().expect("Trail shouldn't be empty");
+            self.domains[node] = domain;
+        }
+    }
+}
+
+fn color_bit(label: Label) -> u8 {
+    match label {
+        Label::True => 1,
+        Label::False => 2,
+        Label::Neutral => 4,
+        Label::Null => 0,
+    }
+}
+
+fn bit_color(domain: u8) -> Label {
+    match domain {
+        1 => Label::True,
+        2 => Label::False,
+        4 => Label::Neutral,
+        _ => Label::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn graph(count: usize, edges: &[(usize, usize)]) -> Vec<Node> {
+        let mut nodes: Vec<Node> = (0..count).map(|_| Node {
+            connections: vec![],
+            label: Label::Null,
+            label_whitelist: COLORS.iter().copied().collect::<HashSet<_>>(),
+        }).collect();
+        for &(a, b) in edges {
+            nodes[a].connections.push(b);
+            nodes[b].connections.push(a);
+        }
+        nodes
+    }
+
+    #[test]
+    fn solves_and_verifies_wheel() {
+        //A hub connected to every node of an even cycle. The hub takes one color, the cycle alternates between the other two.
+        let mut nodes = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 0), (4, 0), (4, 1), (4, 2), (4, 3)]);
+        nodes[4].label = Label::Neutral;
+        let labels = solve(&nodes).expect("Wheel with an even rim is 3-colorable");
+        assert_eq!(labels[4], Label::Neutral);
+        assert!(verify_labels(&nodes, &labels).is_empty());
+    }
+
+    #[test]
+    fn rejects_k4() {
+        let nodes = graph(4, &[(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+        assert!(solve(&nodes).is_none());
+        assert_eq!(verify(&nodes).len(), 4);
+    }
+}
@@ -6,22 +6,57 @@ use crate::compiler::token::{AtomSub, AtomType};
 
 use super::code_location::{CodeLocation, LocationValue};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Project {
     pub composite_types: Vec<CompositeTypeSyntax>,
+    pub enums: Vec<EnumSyntax>,
     pub problems: Vec<SubstructureSyntax>,
     pub collections: Vec<CollectionSyntax>,
-    pub solutions: HashMap<String, SubCallSyntax>,
-    pub supers: HashMap<String, usize>
+    pub solutions: Vec<SolutionSyntax>,
+    pub supers: HashMap<String, SuperSyntax>
 }
 
 impl Project {
     pub fn new() -> Self {
         Self::default()
     }
+
+    ///The number of graphs the project compiles to, one for every solution of the problem with the most solutions
+    pub fn solution_count(&self) -> usize {
+        self.problems.iter().map(|problem| self.solutions.iter().filter(|s| s.problem.value == problem.name.value).count()).max().unwrap_or(0).max(1)
+    }
+}
+
+///Finds the sub called by a.method(..) on a value of the given type. Methods are declared in the collection named like the type, or in its snake_case form (IntBe -> int_be)
+pub fn find_method<'a>(collections: &'a [CollectionSyntax], type_name: &str, method: &str) -> Option<(&'a CollectionSyntax, &'a SubstructureSyntax)> {
+    let snake_case = snake_case(type_name);
+    collections.iter()
+        .filter(|c| c.name.value == type_name || c.name.value == snake_case)
+        .find_map(|c| Some((c, c.subs.iter().find(|s| s.name.value == method)?)))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+///One entry of the solution block, giving the inputs of a problem
+#[derive(Debug, Clone)]
+pub struct SolutionSyntax {
+    pub problem: LocationValue<String>,
+    pub arguments: Vec<ExpressionSyntax>,
+    //Values of the witnesses declared in the problem
+    pub witnesses: Vec<FieldAssignSyntax>,
+    pub location: Option<CodeLocation>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct CollectionSyntax {
     pub subs: Vec<SubstructureSyntax>,
     pub name: LocationValue<String>,
@@ -30,11 +65,20 @@ pub struct CollectionSyntax {
 #[derive(Default, Debug, Clone)]
 pub struct SubstructureSyntax {
     pub name: LocationValue<String>,
+    //Names of the compile time integers the sub is generic over
+    pub generics: Vec<LocationValue<String>>,
     pub args: Vec<TypedIdentifierSyntax>,
     pub code: Vec<CodeSyntax>,
     pub result: Option<ExpressionSyntax>
 }
 
+impl SubstructureSyntax {
+    ///Whether the generic is used as the type of an argument, like T in sub fold<T>(array: a, T: init, sub: f). Such generics stand for a type instead of a compile time integer
+    pub fn is_type_generic(&self, generic: &str) -> bool {
+        self.generics.iter().any(|g| g.value == generic) && self.args.iter().any(|arg| arg.type_syntax.names_type(generic))
+    }
+}
+
 impl Hash for SubstructureSyntax {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.name.value.hash(state);
@@ -48,6 +92,18 @@ impl PartialEq for SubstructureSyntax {
 }
 impl Eq for SubstructureSyntax {}
 
+#[derive(Debug, Clone)]
+pub enum IteratorSyntax {
+    //for (i in n) counts from 0 to n - 1, for (x in a) binds the elements of the array a
+    Value(ExpressionSyntax),
+    //for (i in a..b step s), the end is exclusive and the step defaults to 1
+    Range {
+        start: Box<ExpressionSyntax>,
+        end: Box<ExpressionSyntax>,
+        step: Option<Box<ExpressionSyntax>>
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum CodeSyntax {
     ReassignSyntax {
@@ -56,7 +112,9 @@ pub enum CodeSyntax {
     },
     For {
         iterator_variable: LocationValue<String>,
-        iterator_amount: ExpressionSyntax,
+        //The position of the element, bound by enumerate like for ((i, x) in enumerate(a))
+        index_variable: Option<LocationValue<String>>,
+        iterator: IteratorSyntax,
         iterator_body: Vec<CodeSyntax>
     },
     If {
@@ -72,6 +130,16 @@ pub enum CodeSyntax {
         variable: LocationValue<String>,
         value: ExpressionSyntax,
     },
+    //Binds the parts of a tuple or composite to new variables, like let (a, b) = ...
+    LetPattern {
+        pattern: PatternSyntax,
+        value: ExpressionSyntax,
+    },
+    //Reassigns the parts of a tuple or composite, like (a, b) = ...
+    ReassignPattern {
+        pattern: PatternSyntax,
+        value: ExpressionSyntax,
+    },
     Force {
         value: ExpressionSyntax,
         type_syntax: TypeSyntax,
@@ -79,21 +147,94 @@ pub enum CodeSyntax {
     Sub(SubCallSyntax),
     Output {
         expression: ExpressionSyntax
+    },
+    //Raises an error while compiling if the compile time condition is false
+    StaticAssert {
+        condition: ExpressionSyntax,
+        message: Option<ExpressionSyntax>,
+        location: Option<CodeLocation>,
+    },
+    //Raises an error whenever it is compiled
+    CompileError {
+        message: ExpressionSyntax,
+        location: Option<CodeLocation>,
+    },
+    //Returns early from the sub, the rest of the sub is skipped if the return is reached
+    Return {
+        value: ExpressionSyntax
+    },
+    //A free input of a problem, whose value is given in the witness section of the solution
+    Witness {
+        declaration: TypedIdentifierSyntax
+    },
+    //Compiles every arm under the condition that the enum value is one of its variants
+    Match {
+        value: ExpressionSyntax,
+        arms: Vec<MatchArmSyntax>
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArmSyntax {
+    //None for the else arm, which matches every variant not matched by the arms before it
+    pub variants: Option<Vec<LocationValue<String>>>,
+    pub code: Vec<CodeSyntax>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternSyntax {
+    //A variable name in let patterns, any assignable expression in reassignments
+    Single(ExpressionSyntax),
+    Tuple {
+        elements: Vec<PatternSyntax>,
+        location: Option<CodeLocation>,
+    },
+    //Fields can be left out, a field without a pattern is bound to a variable of the same name
+    Composite {
+        type_name: LocationValue<String>,
+        fields: Vec<(LocationValue<String>, PatternSyntax)>,
+        location: Option<CodeLocation>,
+    }
+}
+impl PatternSyntax {
+    pub fn code_location(&self) -> Option<CodeLocation> {
+        match self {
+            Self::Single(expression) => expression.code_location(),
+            Self::Tuple { location, .. } | Self::Composite { location, .. } => location.clone(),
+        }
     }
 }
 
 #[derive(Debug, Clone, EnumAsInner)]
 pub enum TypeSyntax {
     Atom(AtomType),
+    //Compile time integers, written as sup
+    Super,
+    //References to subs, written as sub
+    Sub,
     Set {
         elements: Vec<TypeSyntax>
     },
+    //Generic arguments can be left out to accept the composite with any generic arguments
     Composite {
         name: LocationValue<String>,
+        generics: Vec<ExpressionSyntax>,
     },
+    //Arrays without a size accept arrays of any length
     Array {
         t: Box<TypeSyntax>,
-        size: ExpressionSyntax
+        size: Option<ExpressionSyntax>
+    }
+}
+impl TypeSyntax {
+    //Whether the type or one of its parts is written with the given name
+    fn names_type(&self, name: &str) -> bool {
+        match self {
+            TypeSyntax::Atom(_) | TypeSyntax::Super | TypeSyntax::Sub => false,
+            TypeSyntax::Set { elements } => elements.iter().any(|t| t.names_type(name)),
+            TypeSyntax::Composite { name: type_name, .. } => type_name.value == name,
+            TypeSyntax::Array { t, .. } => t.names_type(name),
+        }
     }
 }
 
@@ -121,16 +262,81 @@ pub enum ExpressionSyntax {
         index: Box<ExpressionSyntax>
     },
     Sub(Box<SubCallSyntax>),
+    //A sub referenced with the sub keyword, like sub bool::and, which can be passed to other subs
+    SubReference {
+        collection: LocationValue<String>,
+        sub: LocationValue<String>,
+    },
     Literal(LocationValue<AtomType>),
+    //a == b, a != b and a ^ b. Booleans and enums are compared atom by atom, integers are compared while compiling
+    Equality {
+        operator: LocationValue<EqualityOperator>,
+        left: Box<ExpressionSyntax>,
+        right: Box<ExpressionSyntax>,
+    },
     CompositeConstructor {
         type_name: LocationValue<String>,
         field_assign: Vec<FieldAssignSyntax>
     }
 }
 
+impl ExpressionSyntax {
+    pub fn code_location(&self) -> Option<CodeLocation> {
+        match self {
+            Self::String(s) => s.location.clone(),
+            Self::Int(i) => i.location.clone(),
+            Self::Variable(v) => v.location.clone(),
+            Self::Literal(l) => l.location.clone(),
+            Self::Tuple(items) | Self::Array(items) => span(items.first()?.code_location(), items.last()?.code_location()),
+            Self::LengthArray { count, base } => span(base.code_location(), count.code_location()),
+            Self::Access { base, field } => span(base.code_location(), field.location.clone()),
+            Self::AccessIdx { base, idx } => span(base.code_location(), idx.location.clone()),
+            Self::IndexOp { base, index } => span(base.code_location(), index.code_location()),
+            //The receiver of a method call comes before its name
+            Self::Sub(sub_call) if matches!(sub_call.location, SubLocation::Method(_)) => sub_call.application.as_ref()?.code_location(),
+            Self::Sub(sub_call) => span(sub_call.location.code_location(), sub_call.application.as_ref().and_then(|a| a.code_location())),
+            Self::SubReference { collection, sub } => span(collection.location.clone(), sub.location.clone()),
+            Self::Equality { left, right, .. } => span(left.code_location(), right.code_location()),
+            Self::CompositeConstructor { type_name, field_assign } => span(type_name.location.clone(), field_assign.last().and_then(|f| f.right.code_location())),
+        }
+    }
+
+    //Whether the expression names a variable or a part of one, as opposed to a computed value like a.add(b).data
+    pub fn is_place(&self) -> bool {
+        match self {
+            Self::Variable(_) => true,
+            Self::Access { base, .. } | Self::AccessIdx { base, .. } | Self::IndexOp { base, .. } => base.is_place(),
+            _ => false
+        }
+    }
+}
+
+//Spans both locations if possible, otherwise returns whichever one is known
+pub fn span(begin: Option<CodeLocation>, end: Option<CodeLocation>) -> Option<CodeLocation> {
+    match (begin, end) {
+        (Some(begin), Some(end)) if begin.path == end.path && begin.section.is_some() && end.section.is_some() => Some(begin.to(&end)),
+        (begin, end) => begin.or(end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualityOperator {
+    Eq,
+    Ne,
+    //Only defined on booleans, where it is the same as !=
+    Xor,
+}
+impl EqualityOperator {
+    pub fn is_negated(self) -> bool {
+        self != Self::Eq
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SubCallSyntax {
     pub location: SubLocation,
+    //Explicit generic arguments, generics that aren't given are inferred from the arguments
+    pub generics: Vec<ExpressionSyntax>,
     pub application: Option<ExpressionSyntax>,
 }
 
@@ -141,14 +347,20 @@ pub enum SubLocation {
         sub: LocationValue<String>,
     },
     Super(LocationValue<String>),
-    Atom(LocationValue<AtomSub>)
+    Atom(LocationValue<AtomSub>),
+    //A call of the sub referenced by a variable, like f(a, b)
+    Reference(LocationValue<String>),
+    //A sub called on a value, like a.add(b). The value is the first argument of the application
+    Method(LocationValue<String>),
 }
 impl SubLocation {
     pub fn code_location(&self) -> Option<CodeLocation> {
         match self {
             SubLocation::Structure { collection, sub } => Some(collection.location.as_ref().unwrap_or(sub.location.as_ref()?).to(sub.location.as_ref()?)),
             SubLocation::Super(location) => location.location.clone(),
-            SubLocation::Atom(location) => location.location.clone()
+            SubLocation::Atom(location) => location.location.clone(),
+            SubLocation::Reference(location) => location.location.clone(),
+            SubLocation::Method(location) => location.location.clone(),
         }
     }
 }
@@ -164,10 +376,69 @@ pub struct FieldAssignSyntax {
     pub right: ExpressionSyntax,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumEncoding {
+    //One boolean per variant, exactly one of them is true
+    #[default]
+    OneHot,
+    //The index of the variant as a binary number, LSB first
+    Binary,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumSyntax {
+    pub name: LocationValue<String>,
+    pub variants: Vec<LocationValue<String>>,
+    pub encoding: EnumEncoding,
+}
+impl EnumSyntax {
+    pub fn variant_index(&self, variant: &str) -> Option<usize> {
+        self.variants.iter().position(|v| v.value == variant)
+    }
+
+    //Number of booleans a value of the enum consists of
+    pub fn bit_count(&self) -> usize {
+        match self.encoding {
+            EnumEncoding::OneHot => self.variants.len(),
+            EnumEncoding::Binary => (usize::BITS - self.variants.len().saturating_sub(1).leading_zeros()) as usize,
+        }
+    }
+
+    pub fn encode(&self, index: usize) -> Vec<bool> {
+        (0..self.bit_count()).map(|bit| match self.encoding {
+            EnumEncoding::OneHot => bit == index,
+            EnumEncoding::Binary => index >> bit & 1 == 1,
+        }).collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CompositeTypeSyntax {
     pub name: LocationValue<String>,
-    pub fields: Vec<TypedIdentifierSyntax>
+    pub generics: Vec<LocationValue<String>>,
+    pub fields: Vec<CompositeFieldSyntax>
+}
+
+#[derive(Debug, Clone)]
+pub struct CompositeFieldSyntax {
+    pub name: LocationValue<String>,
+    pub type_syntax: TypeSyntax,
+    //Optional fields can be left out in constructors, the field is then missing from the value
+    pub optional: bool,
+    //Fields with a default value can be left out in constructors as well
+    pub default: Option<ExpressionSyntax>,
+}
+
+impl CompositeFieldSyntax {
+    pub fn can_be_omitted(&self) -> bool {
+        self.optional || self.default.is_some()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SuperSyntax {
+    pub name: LocationValue<String>,
+    pub value: ExpressionSyntax,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -89,17 +89,24 @@ pub enum Keyword {
     If,
     In,
     Let,
+    Impl,
     For,
+    Enum,
     Else,
+    Match,
     Super,  
     Force,  
     Output,
+    Return,
     Import,
     Problem,
+    Witness,
     Solution,
     Composite,
     Collection,
     SubStructure,
+    CompileError,
+    StaticAssert,
 } //Sorting this by length was not intentional, but lets go
 #[derive(Debug, Clone, EnumAsInner, PartialEq, Eq)]
 
@@ -109,8 +116,25 @@ pub enum Delimiter {
     DoubleColon,
     Comma,
     Period,
+    DoublePeriod,
     Semicolon,
     Equals,
+    QuestionMark,
+    LessThan,
+    GreaterThan,
+    LessThanOrEquals,
+    GreaterThanOrEquals,
+    DoubleEquals,
+    NotEquals,
+    ExclamationMark,
+    Ampersand,
+    Pipe,
+    Caret,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
     ThickArrowRight,
     ThinArrowRight,
 }
@@ -1,27 +1,1259 @@
-use std::collections::HashMap;
+/*
+Semantic analysis of a parsed project. Resolves the types of sub arguments, composite fields, let bindings and sub results,
+and reports mismatches with their location before any IR is generated.
+*/
 
-use super::{compilation::Compilation, syntax::{CodeSyntax, Project, SubstructureSyntax, TypeSyntax}};
+use std::{collections::HashMap, fmt::Display};
 
-pub fn type_check(project: &Project, compilation: &mut Compilation) {
-    for collection in &project.collections {
-        collection.subs.iter().for_each(|s| check_sub(s, compilation));
+use super::{code_location::{CodeLocation, LocationValue}, compilation::Compilation, scope::Scope, diagnostic::{Diagnostic, DiagnosticPipelineLocation, DiagnosticType}, syntax::{CodeSyntax, CollectionSyntax, EnumSyntax, EqualityOperator, ExpressionSyntax, IteratorSyntax, PatternSyntax, Project, SubCallSyntax, SubLocation, SubstructureSyntax, TypeSyntax, TypedIdentifierSyntax, find_method, span}, token::AtomSub};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Bool,
+    //A boolean known at compile time. Can be used wherever a bool is expected.
+    SuperBool,
+    SuperInt,
+    SuperString,
+    Array(Box<Type>),
+    Tuple(Vec<Type>),
+    //The generic arguments, which are None if they aren't known before compiling, and the names of the optional fields the value is known to be missing
+    Composite(String, Vec<Option<usize>>, Vec<String>),
+    Enum(String),
+    //A reference to a sub. The collection and name of the sub are known for references made in the same sub, but not for arguments
+    Sub(Option<(String, String)>),
+    //The type couldn't be determined, for example because of a previous error. Compatible with every other type.
+    Unknown,
+}
+
+impl Type {
+    pub fn is_compatible(&self, other: &Type) -> bool {
+        match (self.unwrap_single(), other.unwrap_single()) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Bool, Type::SuperBool) => true,
+            (Type::Array(a), Type::Array(b)) => a.is_compatible(b),
+            (Type::Composite(a, a_generics, _), Type::Composite(b, b_generics, _)) => a == b && a_generics.iter().zip(b_generics).all(|(a, b)| a.is_none() || b.is_none() || a == b),
+            (Type::Sub(_), Type::Sub(_)) => true,
+            (Type::Tuple(a), Type::Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.is_compatible(b)),
+            (a, b) => a == b,
+        }
     }
+
+    //A tuple with a single element is treated like the element itself
+    fn unwrap_single(&self) -> &Type {
+        match self {
+            Type::Tuple(t) if t.len() == 1 => t[0].unwrap_single(),
+            _ => self
+        }
+    }
+
+    fn is_forceable(&self) -> bool {
+        match self {
+            Type::Bool | Type::SuperBool | Type::Unknown => true,
+            Type::Tuple(t) => t.iter().all(|t| t.is_forceable()),
+            _ => false
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::SuperBool => write!(f, "super bool"),
+            Type::SuperInt => write!(f, "sup"),
+            Type::SuperString => write!(f, "super string"),
+            Type::Array(t) => write!(f, "[{t}]"),
+            Type::Tuple(t) => write!(f, "({})", t.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")),
+            Type::Composite(name, generics, _) if generics.iter().any(|g| g.is_some()) => {
+                let generics: Vec<_> = generics.iter().map(|g| g.map_or("_".to_string(), |g| g.to_string())).collect();
+                write!(f, "{name}<{}>", generics.join(", "))
+            }
+            Type::Composite(name, _, _) | Type::Enum(name) => write!(f, "{name}"),
+            Type::Sub(_) => write!(f, "sub"),
+            Type::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+pub fn type_check(project: &Project, compilation: &mut Compilation) {
+    let mut checker = TypeChecker::new(project, compilation);
+    checker.check_project();
+}
+
+struct TypeChecker<'a> {
+    project: &'a Project,
+    compilation: &'a mut Compilation,
+    //Fields of the composite types, with their type and whether they can be left out in constructors
+    composites: HashMap<String, Vec<(String, Type, bool)>>,
+    //Result types of the subs that have been checked, by collection and sub name. None while the sub is still being checked.
+    sub_results: HashMap<(String, String), Option<Type>>,
+    //Types of the super constants, None while a super is being checked
+    super_types: HashMap<String, Option<Type>>,
+    //Types of the values returned early by each sub that is currently being checked
+    returns: Vec<Vec<(Type, Option<CodeLocation>)>>,
+    //Whether the sub being checked is a problem, only problems can declare witnesses
+    in_problem: bool,
+    //Whether a loop body is being checked, witnesses can't be declared in one
+    in_loop: bool,
+    //The generics used as types by each sub that is currently being checked, they stand for any type
+    type_generics: Vec<Vec<String>>,
 }
 
-fn check_sub(sub: &SubstructureSyntax, compilation: &mut Compilation) {
-    let mut vars: HashMap<&str, TypeSyntax> = HashMap::new();
-    for input in &sub.args {
-        vars.insert(&input.name.value, input.type_syntax.to_owned());
+impl<'a> TypeChecker<'a> {
+    fn new(project: &'a Project, compilation: &'a mut Compilation) -> Self {
+        let mut checker = Self {
+            project,
+            compilation,
+            composites: HashMap::new(),
+            sub_results: HashMap::new(),
+            super_types: HashMap::new(),
+            returns: vec![],
+            in_problem: false,
+            in_loop: false,
+            type_generics: vec![],
+        };
+        for composite in &project.composite_types {
+            let fields = composite.fields.iter().map(|field| (field.name.value.clone(), checker.lookup_type(&field.type_syntax), field.can_be_omitted())).collect();
+            checker.composites.insert(composite.name.value.clone(), fields);
+        }
+        checker
+    }
+
+    fn error(&mut self, message: String, location: Option<CodeLocation>) {
+        self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Error, message, location, DiagnosticPipelineLocation::SemanticAnalysis));
+    }
+
+    fn expect(&mut self, expected: &Type, found: &Type, location: Option<CodeLocation>) {
+        if !expected.is_compatible(found) {
+            self.error(format!("Mismatched types: expected {expected}, found {found}"), location);
+        }
+    }
+
+    fn check_project(&mut self) {
+        let project = self.project;
+        let mut super_names: Vec<&String> = project.supers.keys().collect();
+        super_names.sort();
+        for name in super_names {
+            self.super_type(name);
+        }
+        for composite in &project.composite_types {
+            let mut generics = Scope::new();
+            for generic in &composite.generics {
+                generics.insert(generic.value.clone(), Type::SuperInt);
+            }
+            for field in &composite.fields {
+                self.check_type_syntax(&field.type_syntax, &mut generics);
+                if let Some(default) = &field.default {
+                    let field_type = self.lookup_type(&field.type_syntax);
+                    let default_type = self.check_expression(default, &mut generics);
+                    if !field_type.is_compatible(&default_type) {
+                        self.error(format!("Default value of field \"{}\" of {} expects {field_type}, found {default_type}", field.name.value, composite.name.value), default.code_location());
+                    }
+                }
+            }
+        }
+        for enum_syntax in &project.enums {
+            if enum_syntax.variants.is_empty() {
+                self.error(format!("Enum {} needs at least one variant", enum_syntax.name.value), enum_syntax.name.location.clone());
+            }
+            for (i, variant) in enum_syntax.variants.iter().enumerate() {
+                if enum_syntax.variants[..i].iter().any(|v| v.value == variant.value) {
+                    self.error(format!("Variant {} of {} is defined more than once", variant.value, enum_syntax.name.value), variant.location.clone());
+                }
+            }
+        }
+        for collection in &project.collections {
+            for sub in &collection.subs {
+                self.sub_result(collection, sub);
+            }
+        }
+        self.in_problem = true;
+        for problem in &project.problems {
+            self.check_sub(problem);
+            let mut witnesses = vec![];
+            witness_declarations(&problem.code, &mut witnesses);
+            for (i, witness) in witnesses.iter().enumerate() {
+                if witnesses[..i].iter().any(|w| w.name.value == witness.name.value) {
+                    self.error(format!("Witness {} is declared more than once", witness.name.value), witness.name.location.clone());
+                }
+            }
+        }
+        self.in_problem = false;
+        self.check_solutions();
+    }
+
+    //Checks that every solution belongs to a problem and gives it the right inputs
+    fn check_solutions(&mut self) {
+        let project = self.project;
+        for solution in &project.solutions {
+            let Some(problem) = project.problems.iter().find(|p| p.name.value == solution.problem.value) else {
+                self.error(format!("Solution for unknown problem \"{}\"", solution.problem.value), solution.problem.location.clone());
+                continue;
+            };
+            if solution.arguments.len() != problem.args.len() {
+                self.error(format!("Problem {} expects {} arguments, but the solution gives {}", problem.name.value, problem.args.len(), solution.arguments.len()), solution.location.clone());
+                continue;
+            }
+            for (argument, arg) in solution.arguments.iter().zip(&problem.args) {
+                let expected = self.lookup_type(&arg.type_syntax);
+                let found = self.check_expression(argument, &mut Scope::new());
+                let integer = found == Type::SuperInt && self.holds_integer(&expected);
+                if !expected.is_compatible(&found) && !integer {
+                    self.error(format!("Argument {} of problem {} expects {expected}, found {found}", arg.name.value, problem.name.value), argument.code_location());
+                }
+            }
+            let mut witnesses = vec![];
+            witness_declarations(&problem.code, &mut witnesses);
+            for (i, witness) in solution.witnesses.iter().enumerate() {
+                if solution.witnesses[..i].iter().any(|w| w.left.value == witness.left.value) {
+                    self.error(format!("Witness {} is given more than once", witness.left.value), witness.left.location.clone());
+                }
+                let Some(declaration) = witnesses.iter().find(|w| w.name.value == witness.left.value) else {
+                    self.error(format!("Problem {} has no witness \"{}\"", problem.name.value, witness.left.value), witness.left.location.clone());
+                    continue;
+                };
+                let expected = self.lookup_type(&declaration.type_syntax);
+                let found = self.check_expression(&witness.right, &mut Scope::new());
+                let integer = found == Type::SuperInt && self.holds_integer(&expected);
+                if !expected.is_compatible(&found) && !integer {
+                    self.error(format!("Witness {} of problem {} expects {expected}, found {found}", witness.left.value, problem.name.value), witness.right.code_location());
+                }
+            }
+        }
+        for problem in &project.problems {
+            if !problem.args.is_empty() && !project.solutions.iter().any(|s| s.problem.value == problem.name.value) {
+                self.compilation.add_diagnostic(Diagnostic::new(DiagnosticType::Warning, format!("Problem {} has no solution, so its inputs are left unlabelled", problem.name.value), problem.name.location.clone(), DiagnosticPipelineLocation::SemanticAnalysis));
+            }
+        }
+    }
+
+    //Whether an integer can be given for the type in a solution, which is true for arrays of booleans and composites with only such a field
+    fn holds_integer(&self, t: &Type) -> bool {
+        match t {
+            Type::Array(t) => matches!(t.as_ref(), Type::Bool),
+            Type::Composite(name, _, _) => matches!(&self.composites.get(name).map(|fields| fields.as_slice()), Some([(_, t, _)]) if self.holds_integer(t)),
+            _ => false
+        }
+    }
+
+    //Resolves a type without reporting unknown type names
+    fn lookup_type(&self, type_syntax: &TypeSyntax) -> Type {
+        match type_syntax {
+            TypeSyntax::Atom(_) => Type::Bool,
+            TypeSyntax::Super => Type::SuperInt,
+            TypeSyntax::Sub => Type::Sub(None),
+            TypeSyntax::Set { elements } => Type::Tuple(elements.iter().map(|t| self.lookup_type(t)).collect()),
+            TypeSyntax::Array { t, .. } => Type::Array(Box::new(self.lookup_type(t))),
+            TypeSyntax::Composite { name, generics } => match name.value.as_str() {
+                "bool" => Type::Bool,
+                "array" => Type::Array(Box::new(Type::Unknown)),
+                name if self.project.composite_types.iter().any(|c| c.name.value == name) => {
+                    let generics = generics.iter().map(|g| match g {
+                        ExpressionSyntax::Int(i) => Some(i.value),
+                        _ => None
+                    }).collect();
+                    Type::Composite(name.to_owned(), generics, vec![])
+                }
+                name if self.project.enums.iter().any(|e| e.name.value == name) => Type::Enum(name.to_owned()),
+                _ => Type::Unknown
+            }
+        }
+    }
+
+    //Reports every type name in the type that can't be resolved, and array sizes that aren't compile time integers
+    fn check_type_syntax(&mut self, type_syntax: &TypeSyntax, variables: &mut Scope<Type>) {
+        match type_syntax {
+            TypeSyntax::Atom(_) | TypeSyntax::Super | TypeSyntax::Sub => {}
+            TypeSyntax::Set { elements } => elements.iter().for_each(|t| self.check_type_syntax(t, variables)),
+            TypeSyntax::Array { t, size } => {
+                self.check_type_syntax(t, variables);
+                if let Some(size) = size {
+                    let size_type = self.check_expression(size, variables);
+                    self.expect(&Type::SuperInt, &size_type, size.code_location());
+                }
+            }
+            TypeSyntax::Composite { name, generics } => {
+                let is_type_generic = self.type_generics.last().is_some_and(|generics| generics.contains(&name.value));
+                if !is_type_generic && self.lookup_type(type_syntax) == Type::Unknown {
+                    self.error(format!("Unknown type \"{}\"", name.value), name.location.clone());
+                }
+                let project = self.project;
+                let parameter_count = project.composite_types.iter().find(|c| c.name.value == name.value).map_or(0, |c| c.generics.len());
+                //Unknown types are already reported, so their generic arguments aren't counted
+                if !generics.is_empty() && generics.len() != parameter_count && self.lookup_type(type_syntax) != Type::Unknown {
+                    self.error(format!("{} expects {parameter_count} generic arguments, found {}", name.value, generics.len()), name.location.clone());
+                }
+                self.check_generic_arguments(generics, variables);
+            }
+        }
+    }
+
+    //Whether the number of booleans of the type is known without a value, which arrays without length and composites without generic arguments aren't
+    fn is_sized(&self, type_syntax: &TypeSyntax) -> bool {
+        match type_syntax {
+            TypeSyntax::Atom(_) | TypeSyntax::Super | TypeSyntax::Sub => true,
+            TypeSyntax::Set { elements } => elements.iter().all(|t| self.is_sized(t)),
+            TypeSyntax::Array { t, size } => size.is_some() && self.is_sized(t),
+            TypeSyntax::Composite { name, generics } => {
+                let project = self.project;
+                project.composite_types.iter().find(|c| c.name.value == name.value).is_none_or(|c| generics.len() == c.generics.len() && c.fields.iter().all(|f| self.is_sized(&f.type_syntax)))
+            }
+        }
+    }
+
+    fn check_generic_arguments(&mut self, generics: &[ExpressionSyntax], variables: &mut Scope<Type>) {
+        for generic in generics {
+            let generic_type = self.check_expression(generic, variables);
+            self.expect(&Type::SuperInt, &generic_type, generic.code_location());
+        }
+    }
+
+    fn super_type(&mut self, name: &str) -> Type {
+        let project = self.project;
+        let super_syntax = match project.supers.get(name) {
+            Some(s) => s,
+            None => return Type::Unknown
+        };
+        match self.super_types.get(name) {
+            Some(Some(t)) => return t.clone(),
+            Some(None) => {
+                self.error(format!("Super {name} depends on itself"), super_syntax.name.location.clone());
+                return Type::Unknown;
+            }
+            None => {}
+        }
+        self.super_types.insert(name.to_owned(), None);
+        let mut t = self.check_expression(&super_syntax.value, &mut Scope::new());
+        if !matches!(t, Type::SuperInt | Type::SuperString | Type::SuperBool | Type::Unknown) {
+            self.error(format!("Super {name} must be a compile time value, found {t}"), super_syntax.value.code_location());
+            t = Type::Unknown;
+        }
+        self.super_types.insert(name.to_owned(), Some(t.clone()));
+        t
+    }
+
+    fn sub_result(&mut self, collection: &CollectionSyntax, sub: &SubstructureSyntax) -> Type {
+        let key = (collection.name.value.clone(), sub.name.value.clone());
+        match self.sub_results.get(&key) {
+            Some(Some(t)) => return t.clone(),
+            //The sub calls itself, its result isn't known yet
+            Some(None) => return Type::Unknown,
+            None => {}
+        }
+        self.sub_results.insert(key.clone(), None);
+        let result = self.check_sub(sub);
+        self.sub_results.insert(key, Some(result.clone()));
+        result
+    }
+
+    fn check_sub(&mut self, sub: &SubstructureSyntax) -> Type {
+        let mut variables = Scope::new();
+        let (type_generics, generics): (Vec<_>, Vec<_>) = sub.generics.iter().partition(|generic| sub.is_type_generic(&generic.value));
+        for generic in generics {
+            variables.insert(generic.value.clone(), Type::SuperInt);
+        }
+        self.type_generics.push(type_generics.into_iter().map(|generic| generic.value.clone()).collect());
+        for arg in &sub.args {
+            self.check_type_syntax(&arg.type_syntax, &mut variables);
+            variables.insert(arg.name.value.clone(), self.lookup_type(&arg.type_syntax));
+        }
+        self.returns.push(vec![]);
+        self.check_code_block(&sub.code, &mut variables);
+        let result = sub.result.as_ref().map(|result| self.check_expression(result, &mut variables));
+        let returns = self.returns.pop().unwrap_or_default();
+        self.type_generics.pop();
+        let mut result = match result {
+            Some(result) => result,
+            None if returns.is_empty() => return Type::Tuple(vec![]),
+            None => {
+                //Without a result, the value of the last return is the value of the sub
+                if !matches!(sub.code.last(), Some(CodeSyntax::Return { .. })) {
+                    self.error(format!("Sub {} returns early, so it must end with a return or have a result", sub.name.value), sub.name.location.clone());
+                }
+                returns[0].0.clone()
+            }
+        };
+        for (return_type, location) in returns {
+            if return_type.is_compatible(&result) && !result.is_compatible(&return_type) {
+                result = return_type;
+            } else if !result.is_compatible(&return_type) {
+                self.error(format!("Returned value of type {return_type} doesn't match the result of the sub of type {result}"), location);
+            }
+        }
+        result
+    }
+
+    //The type of the values bound to the loop variable
+    fn check_iterator(&mut self, iterator: &IteratorSyntax, variables: &mut Scope<Type>) -> Type {
+        match iterator {
+            IteratorSyntax::Value(value) => {
+                let value_type = self.check_expression(value, variables);
+                match value_type.unwrap_single() {
+                    Type::SuperInt => Type::SuperInt,
+                    Type::Array(t) => *t.clone(),
+                    Type::Unknown => Type::Unknown,
+                    t => {
+                        self.error(format!("Can only iterate over super integers and arrays, found {t}"), value.code_location());
+                        Type::Unknown
+                    }
+                }
+            }
+            IteratorSyntax::Range { start, end, step } => {
+                for bound in [Some(start), Some(end), step.as_ref()].into_iter().flatten() {
+                    let bound_type = self.check_expression(bound, variables);
+                    self.expect(&Type::SuperInt, &bound_type, bound.code_location());
+                }
+                Type::SuperInt
+            }
+        }
+    }
+
+    fn check_code_block(&mut self, block: &Vec<CodeSyntax>, variables: &mut Scope<Type>) {
+        for statement in block {
+            match statement {
+                CodeSyntax::For { iterator_variable, index_variable, iterator, iterator_body } => {
+                    let element_type = self.check_iterator(iterator, variables);
+                    variables.push();
+                    if let Some(index_variable) = index_variable {
+                        variables.insert(index_variable.value.clone(), Type::SuperInt);
+                    }
+                    variables.insert(iterator_variable.value.clone(), element_type);
+                    let in_loop = std::mem::replace(&mut self.in_loop, true);
+                    self.check_code_block(iterator_body, variables);
+                    self.in_loop = in_loop;
+                    variables.pop();
+                }
+                CodeSyntax::ReassignSyntax { variable, value } => {
+                    let value_type = self.check_expression(value, variables);
+                    self.check_reassign(variable, value_type, value.code_location(), variables);
+                }
+                CodeSyntax::LetPattern { pattern, value } => {
+                    let value_type = self.check_expression(value, variables);
+                    self.check_pattern(pattern, value_type, true, variables);
+                }
+                CodeSyntax::ReassignPattern { pattern, value } => {
+                    let value_type = self.check_expression(value, variables);
+                    self.check_pattern(pattern, value_type, false, variables);
+                }
+                CodeSyntax::If { condition, condition_true } => {
+                    self.check_condition(condition, variables);
+                    self.check_block_in_scope(condition_true, variables);
+                }
+                CodeSyntax::IfElse { condition, condition_true, condition_false } => {
+                    self.check_condition(condition, variables);
+                    self.check_block_in_scope(condition_true, variables);
+                    self.check_block_in_scope(condition_false, variables);
+                }
+                CodeSyntax::Let { variable, value } => {
+                    let value_type = self.check_expression(value, variables);
+                    variables.insert(variable.value.clone(), value_type);
+                }
+                CodeSyntax::Witness { declaration } => {
+                    if !self.in_problem {
+                        self.error(format!("Witness {} can only be declared in a problem", declaration.name.value), declaration.name.location.clone());
+                    }
+                    if self.in_loop {
+                        self.error(format!("Witness {} can't be declared in a loop", declaration.name.value), declaration.name.location.clone());
+                    }
+                    self.check_type_syntax(&declaration.type_syntax, variables);
+                    if !self.is_sized(&declaration.type_syntax) {
+                        self.error(format!("Witness {} needs a type of known size, like [bool; 8] or Int<8>", declaration.name.value), declaration.name.location.clone());
+                    }
+                    variables.insert(declaration.name.value.clone(), self.lookup_type(&declaration.type_syntax));
+                }
+                CodeSyntax::Force { value, .. } => {
+                    let value_type = self.check_expression(value, variables);
+                    if !value_type.is_forceable() {
+                        self.error(format!("Can only force boolean values, found {value_type}"), value.code_location());
+                    }
+                }
+                CodeSyntax::Sub(sub_call) => {
+                    self.check_sub_call(sub_call, variables);
+                }
+                CodeSyntax::Output { expression } => {
+                    self.check_expression(expression, variables);
+                }
+                CodeSyntax::StaticAssert { condition, message, .. } => {
+                    let condition_type = self.check_expression(condition, variables);
+                    if !matches!(condition_type.unwrap_single(), Type::SuperBool | Type::Unknown) {
+                        self.error(format!("Expected a compile time condition, found {condition_type}"), condition.code_location());
+                    }
+                    if let Some(message) = message {
+                        self.check_super_message(message, variables);
+                    }
+                }
+                CodeSyntax::CompileError { message, .. } => {
+                    self.check_super_message(message, variables);
+                }
+                CodeSyntax::Return { value } => {
+                    let value_type = self.check_expression(value, variables);
+                    if let Some(returns) = self.returns.last_mut() {
+                        returns.push((value_type, value.code_location()));
+                    }
+                }
+                CodeSyntax::Match { value, arms } => {
+                    let value_type = self.check_expression(value, variables);
+                    let project = self.project;
+                    let enum_syntax = match &value_type {
+                        Type::Enum(name) => project.enums.iter().find(|e| e.name.value == *name),
+                        Type::Unknown => None,
+                        t => {
+                            self.error(format!("Can only match on enums, found {t}"), value.code_location());
+                            None
+                        }
+                    };
+                    let mut matched: Vec<&String> = vec![];
+                    for arm in arms {
+                        for variant in arm.variants.iter().flatten() {
+                            let Some(enum_syntax) = enum_syntax else { continue };
+                            if enum_syntax.variant_index(&variant.value).is_none() {
+                                self.error(format!("Enum {} has no variant \"{}\"", enum_syntax.name.value, variant.value), variant.location.clone());
+                            } else if matched.contains(&&variant.value) {
+                                self.error(format!("Variant {} is matched more than once", variant.value), variant.location.clone());
+                            }
+                            matched.push(&variant.value);
+                        }
+                        self.check_block_in_scope(&arm.code, variables);
+                    }
+                    if let Some(enum_syntax) = enum_syntax {
+                        let missing: Vec<_> = enum_syntax.variants.iter().filter(|v| !matched.contains(&&v.value)).map(|v| v.value.as_str()).collect();
+                        if !missing.is_empty() && arms.last().is_none_or(|arm| arm.variants.is_some()) {
+                            self.error(format!("Match on {} doesn't cover {}", enum_syntax.name.value, missing.join(", ")), value.code_location());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_reassign(&mut self, variable: &ExpressionSyntax, value_type: Type, location: Option<CodeLocation>, variables: &mut Scope<Type>) {
+        if let Some(name) = assigned_super(variable, variables, self.project) {
+            self.error(format!("Super {} is a constant and can't be reassigned.", name.value), name.location.clone());
+        }
+        let variable_type = self.check_expression(variable, variables);
+        //A compile time bool stops being one once a runtime value is assigned to it
+        if let (ExpressionSyntax::Variable(name), Type::SuperBool, Type::Bool) = (variable, variable_type.unwrap_single(), value_type.unwrap_single()) {
+            if let Some(t) = variables.get_mut(&name.value) {
+                *t = Type::Bool;
+            }
+            return;
+        }
+        //The optional fields of a reassigned composite are the ones of its new value, and a reassigned reference calls the new sub
+        let replaces_type = match (variable_type.unwrap_single(), value_type.unwrap_single()) {
+            (Type::Composite(..), Type::Composite(..)) => variable_type.is_compatible(&value_type),
+            (Type::Sub(_), Type::Sub(_)) => true,
+            _ => false
+        };
+        if let (ExpressionSyntax::Variable(name), true) = (variable, replaces_type) {
+            if let Some(t) = variables.get_mut(&name.value) {
+                *t = value_type;
+            }
+            return;
+        }
+        if !variable_type.is_compatible(&value_type) {
+            self.error(format!("Can't assign a value of type {value_type} to a variable of type {variable_type}"), location);
+        }
+    }
+
+    //Binds or reassigns every part of the pattern to the matching part of the value type
+    fn check_pattern(&mut self, pattern: &PatternSyntax, value_type: Type, is_let: bool, variables: &mut Scope<Type>) {
+        match pattern {
+            PatternSyntax::Single(ExpressionSyntax::Variable(name)) if is_let => {
+                variables.insert(name.value.clone(), value_type);
+            }
+            PatternSyntax::Single(variable) => {
+                self.check_reassign(variable, value_type, variable.code_location(), variables);
+            }
+            PatternSyntax::Tuple { elements, location } => {
+                let types = match value_type {
+                    Type::Tuple(types) if types.len() == elements.len() => types,
+                    Type::Unknown => vec![Type::Unknown; elements.len()],
+                    value_type => {
+                        self.error(format!("Can't destructure a value of type {value_type} into a tuple of {} elements", elements.len()), location.clone());
+                        vec![Type::Unknown; elements.len()]
+                    }
+                };
+                for (element, element_type) in elements.iter().zip(types) {
+                    self.check_pattern(element, element_type, is_let, variables);
+                }
+            }
+            PatternSyntax::Composite { type_name, fields, location } => {
+                let absent = match &value_type {
+                    Type::Composite(_, _, absent) => absent.clone(),
+                    _ => vec![]
+                };
+                let composite_fields = match &value_type {
+                    Type::Composite(name, _, _) if *name == type_name.value => self.composites.get(name).cloned(),
+                    Type::Unknown => None,
+                    value_type => {
+                        self.error(format!("Can't destructure a value of type {value_type} as {}", type_name.value), location.clone());
+                        None
+                    }
+                };
+                for (field, field_pattern) in fields {
+                    let field_type = match &composite_fields {
+                        Some(composite_fields) => match composite_fields.iter().find(|f| f.0 == field.value) {
+                            Some(_) if absent.contains(&field.value) => {
+                                self.error(format!("Optional field \"{}\" of {} wasn't assigned", field.value, type_name.value), field.location.clone());
+                                Type::Unknown
+                            }
+                            Some(f) => f.1.clone(),
+                            None => {
+                                self.error(format!("Composite type {} has no field \"{}\"", type_name.value, field.value), field.location.clone());
+                                Type::Unknown
+                            }
+                        },
+                        None => Type::Unknown
+                    };
+                    self.check_pattern(field_pattern, field_type, is_let, variables);
+                }
+            }
+        }
+    }
+
+    //The enum the expression refers to, if it is the name of an enum rather than a variable
+    fn referenced_enum(&self, expression: &ExpressionSyntax, variables: &Scope<Type>) -> Option<&'a EnumSyntax> {
+        let project = self.project;
+        match expression {
+            ExpressionSyntax::Variable(name) if variables.get(&name.value).is_none() && !project.supers.contains_key(&name.value) => project.enums.iter().find(|e| e.name.value == name.value),
+            _ => None
+        }
+    }
+
+    fn check_block_in_scope(&mut self, block: &Vec<CodeSyntax>, variables: &mut Scope<Type>) {
+        variables.push();
+        self.check_code_block(block, variables);
+        variables.pop();
     }
-    for s in &sub.code {
-        match s {
-            CodeSyntax::For { iterator_variable, iterator_amount, iterator_body } => todo!(
 
-            ),
-            _ => todo!()
+    fn check_super_message(&mut self, message: &ExpressionSyntax, variables: &mut Scope<Type>) {
+        let message_type = self.check_expression(message, variables);
+        if !matches!(message_type.unwrap_single(), Type::SuperString | Type::SuperInt | Type::SuperBool | Type::Unknown) {
+            self.error(format!("Expected a compile time message, found {message_type}"), message.code_location());
+        }
+    }
+
+    fn check_condition(&mut self, condition: &ExpressionSyntax, variables: &mut Scope<Type>) {
+        let condition_type = self.check_expression(condition, variables);
+        if !Type::Bool.is_compatible(&condition_type) {
+            self.error(format!("Expected a condition of type bool, found {condition_type}"), condition.code_location());
+        }
+    }
+
+    fn check_expression(&mut self, expression: &ExpressionSyntax, variables: &mut Scope<Type>) -> Type {
+        match expression {
+            ExpressionSyntax::String(_) => Type::SuperString,
+            ExpressionSyntax::Int(_) => Type::SuperInt,
+            ExpressionSyntax::Literal(_) => Type::Bool,
+            ExpressionSyntax::Tuple(items) => Type::Tuple(items.iter().map(|item| self.check_expression(item, variables)).collect()),
+            ExpressionSyntax::Array(items) => {
+                let mut element_type = Type::Unknown;
+                for item in items {
+                    let item_type = self.check_expression(item, variables);
+                    if item_type.is_compatible(&element_type) && !element_type.is_compatible(&item_type) {
+                        //Widen the element type, like super bools to bools
+                        element_type = item_type;
+                    } else if !element_type.is_compatible(&item_type) {
+                        self.error(format!("Array elements have mismatching types: expected {element_type}, found {item_type}"), item.code_location());
+                    } else if element_type == Type::Unknown {
+                        element_type = item_type;
+                    }
+                }
+                Type::Array(Box::new(element_type))
+            }
+            ExpressionSyntax::LengthArray { count, base } => {
+                let count_type = self.check_expression(count, variables);
+                self.expect(&Type::SuperInt, &count_type, count.code_location());
+                Type::Array(Box::new(self.check_expression(base, variables)))
+            }
+            ExpressionSyntax::Variable(name) => {
+                match variables.get(&name.value) {
+                    Some(t) => t.clone(),
+                    None if self.project.supers.contains_key(&name.value) => self.super_type(&name.value),
+                    None => {
+                        self.error(format!("Variable {} not found in current scope.", name.value), name.location.clone());
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionSyntax::Access { base, field } => {
+                //Variants of enums, like Color.Red
+                if let Some(enum_syntax) = self.referenced_enum(base, variables) {
+                    if enum_syntax.variant_index(&field.value).is_none() {
+                        self.error(format!("Enum {} has no variant \"{}\"", enum_syntax.name.value, field.value), field.location.clone());
+                    }
+                    return Type::Enum(enum_syntax.name.value.clone());
+                }
+                let base_type = self.check_expression(base, variables);
+                match base_type.unwrap_single() {
+                    Type::Unknown => Type::Unknown,
+                    Type::Composite(name, _, absent) => {
+                        match self.composites.get(name).and_then(|fields| fields.iter().find(|f| f.0 == field.value)) {
+                            Some(_) if absent.contains(&field.value) => {
+                                self.error(format!("Optional field \"{}\" of {name} wasn't assigned", field.value), field.location.clone());
+                                Type::Unknown
+                            }
+                            Some((_, t, _)) => t.clone(),
+                            None => {
+                                self.error(format!("Composite type {name} has no field \"{}\"", field.value), field.location.clone());
+                                Type::Unknown
+                            }
+                        }
+                    }
+                    t => {
+                        self.error(format!("Tried to access field \"{}\" on a value of type {t}, which doesn't have fields.", field.value), field.location.clone());
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionSyntax::AccessIdx { base, idx } => {
+                let base_type = self.check_expression(base, variables);
+                match base_type {
+                    Type::Unknown => Type::Unknown,
+                    Type::Tuple(items) => {
+                        match items.get(idx.value) {
+                            Some(t) => t.clone(),
+                            None => {
+                                self.error(format!("Index {} is out of bounds for tuple with size {}", idx.value, items.len()), idx.location.clone());
+                                Type::Unknown
+                            }
+                        }
+                    }
+                    t => {
+                        self.error(format!("Can't index access a value of type {t}"), idx.location.clone());
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionSyntax::IndexOp { base, index } => {
+                let index_type = self.check_expression(index, variables);
+                self.expect(&Type::SuperInt, &index_type, index.code_location());
+                let base_type = self.check_expression(base, variables);
+                match base_type.unwrap_single() {
+                    Type::Unknown => Type::Unknown,
+                    Type::Array(t) => *t.clone(),
+                    t => {
+                        self.error(format!("Can't index a value of type {t}"), base.code_location());
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionSyntax::Sub(sub_call) => self.check_sub_call(sub_call, variables),
+            ExpressionSyntax::Equality { operator, left, right } => {
+                let left_type = self.check_expression(left, variables);
+                let right_type = self.check_expression(right, variables);
+                //Whichever side has a known type decides what is compared
+                let operand_type = match left_type.unwrap_single() {
+                    Type::Unknown => right_type.unwrap_single().clone(),
+                    t => t.clone()
+                };
+                match operand_type {
+                    Type::Unknown => Type::Unknown,
+                    Type::SuperInt | Type::Enum(_) if operator.value != EqualityOperator::Xor => {
+                        self.expect(&operand_type, &left_type, left.code_location());
+                        self.expect(&operand_type, &right_type, right.code_location());
+                        if operand_type == Type::SuperInt { Type::SuperBool } else { Type::Bool }
+                    }
+                    Type::Bool | Type::SuperBool => {
+                        self.expect(&Type::Bool, &left_type, left.code_location());
+                        self.expect(&Type::Bool, &right_type, right.code_location());
+                        if *left_type.unwrap_single() == Type::SuperBool && *right_type.unwrap_single() == Type::SuperBool { Type::SuperBool } else { Type::Bool }
+                    }
+                    t if operator.value == EqualityOperator::Xor => {
+                        self.error(format!("^ is only defined on booleans, found {t}"), operator.location.clone());
+                        Type::Unknown
+                    }
+                    t => {
+                        self.error(format!("Values of type {t} can't be compared"), operator.location.clone());
+                        Type::Unknown
+                    }
+                }
+            }
+            ExpressionSyntax::SubReference { collection, sub } => {
+                let location = span(collection.location.clone(), sub.location.clone());
+                match self.find_sub(collection, sub, location) {
+                    Some(_) => Type::Sub(Some((collection.value.clone(), sub.value.clone()))),
+                    None => Type::Unknown
+                }
+            }
+            ExpressionSyntax::CompositeConstructor { type_name, field_assign } => {
+                let fields = self.composites.get(&type_name.value).cloned();
+                let mut missing_fields = match &fields {
+                    Some(fields) => fields.clone(),
+                    None => {
+                        self.error(format!("Couldn't find type {}", type_name.value), type_name.location.clone());
+                        vec![]
+                    }
+                };
+                for assign in field_assign {
+                    let value_type = self.check_expression(&assign.right, variables);
+                    if fields.is_none() {
+                        continue;
+                    }
+                    match missing_fields.iter().position(|f| f.0 == assign.left.value) {
+                        Some(i) => {
+                            let (_, field_type, _) = missing_fields.remove(i);
+                            if !field_type.is_compatible(&value_type) {
+                                self.error(format!("Field \"{}\" of {} expects {field_type}, found {value_type}", assign.left.value, type_name.value), assign.right.code_location());
+                            }
+                        }
+                        None => {
+                            self.error(format!("Field \"{}\" has either already been assigned, or is not in the composite type.", assign.left.value), assign.left.location.clone());
+                        }
+                    }
+                }
+                if fields.is_none() {
+                    return Type::Unknown;
+                }
+                //Omitted fields without a default are missing from the value
+                let project = self.project;
+                let absent = project.composite_types.iter().find(|c| c.name.value == type_name.value).map(|composite| {
+                    composite.fields.iter().filter(|f| f.default.is_none() && missing_fields.iter().any(|m| m.0 == f.name.value)).map(|f| f.name.value.clone()).collect()
+                }).unwrap_or_default();
+                missing_fields.retain(|f| !f.2);
+                if !missing_fields.is_empty() {
+                    let names: Vec<_> = missing_fields.iter().map(|f| f.0.as_str()).collect();
+                    self.error(format!("Missing fields of {}: {}", type_name.value, names.join(", ")), type_name.location.clone());
+                }
+                Type::Composite(type_name.value.clone(), vec![], absent)
+            }
+        }
+    }
+
+    fn find_sub(&mut self, collection: &LocationValue<String>, sub: &LocationValue<String>, location: Option<CodeLocation>) -> Option<(&'a CollectionSyntax, &'a SubstructureSyntax)> {
+        let project = self.project;
+        let Some(collection_ref) = project.collections.iter().find(|c| c.name.value == collection.value) else {
+            self.error(format!("Couldn't find collection \"{}\"", collection.value), collection.location.clone());
+            return None;
+        };
+        let Some(sub_ref) = collection_ref.subs.iter().find(|s| s.name.value == sub.value) else {
+            self.error(format!("Couldn't find sub \"{}\" in collection \"{}\"", sub.value, collection.value), location);
+            return None;
+        };
+        Some((collection_ref, sub_ref))
+    }
+
+    fn check_sub_call(&mut self, sub_call: &SubCallSyntax, variables: &mut Scope<Type>) -> Type {
+        let args: Vec<(Type, Option<CodeLocation>)> = match &sub_call.application {
+            None => vec![],
+            Some(ExpressionSyntax::Tuple(items)) => items.iter().map(|item| (self.check_expression(item, variables), item.code_location())).collect(),
+            Some(application) => {
+                let location = application.code_location();
+                match self.check_expression(application, variables) {
+                    //Tuple values are spread over the arguments
+                    Type::Tuple(items) => items.into_iter().map(|t| (t, location.clone())).collect(),
+                    t => vec![(t, location)]
+                }
+            }
+        };
+        let call_location = sub_call.location.code_location();
+        self.check_generic_arguments(&sub_call.generics, variables);
+
+        //The sub that is called, if it is a sub of a collection
+        let mut called = None;
+        let (params, result) = match &sub_call.location {
+            SubLocation::Super(name) => {
+                match super_signature(&name.value) {
+                    Some(signature) => signature,
+                    None => {
+                        self.error(format!("Unknown super function: {}", name.value), name.location.clone());
+                        return Type::Unknown;
+                    }
+                }
+            }
+            SubLocation::Atom(atom) => {
+                //Atom subs on compile time booleans are evaluated while compiling
+                let result = if !args.is_empty() && args.iter().all(|(t, _)| *t.unwrap_single() == Type::SuperBool) { Type::SuperBool } else { Type::Bool };
+                match atom.value {
+                    AtomSub::Not => (vec![Type::Bool], result),
+                    AtomSub::Or => (vec![Type::Bool, Type::Bool], result),
+                }
+            }
+            //Subs passed as arguments are only known while compiling, so only calls of references made in this sub can be checked
+            SubLocation::Reference(name) => {
+                let referenced = match variables.get(&name.value) {
+                    Some(Type::Sub(referenced)) => referenced.clone(),
+                    Some(Type::Unknown) => None,
+                    Some(t) => {
+                        let t = t.clone();
+                        self.error(format!("{} is not a sub, found {t}", name.value), name.location.clone());
+                        None
+                    }
+                    None => {
+                        self.error(format!("Variable {} not found in current scope.", name.value), name.location.clone());
+                        None
+                    }
+                };
+                let Some((collection, sub)) = referenced else {
+                    return Type::Unknown;
+                };
+                let collection = LocationValue::new(name.location.clone(), collection);
+                let sub = LocationValue::new(name.location.clone(), sub);
+                let Some((collection_ref, sub_ref)) = self.find_sub(&collection, &sub, call_location.clone()) else {
+                    return Type::Unknown;
+                };
+                called = Some(sub_ref);
+                let params = sub_ref.args.iter().map(|arg| self.lookup_type(&arg.type_syntax)).collect();
+                (params, self.sub_result(collection_ref, sub_ref))
+            }
+            SubLocation::Structure { collection, sub } => {
+                let Some((collection_ref, sub_ref)) = self.find_sub(collection, sub, call_location.clone()) else {
+                    return Type::Unknown;
+                };
+                if sub_call.generics.len() > sub_ref.generics.len() {
+                    self.error(format!("{} expects at most {} generic arguments, found {}", sub.value, sub_ref.generics.len(), sub_call.generics.len()), call_location.clone());
+                }
+                called = Some(sub_ref);
+                let params = sub_ref.args.iter().map(|arg| self.lookup_type(&arg.type_syntax)).collect();
+                (params, self.sub_result(collection_ref, sub_ref))
+            }
+            SubLocation::Method(method) => {
+                let type_name = match args.first().map(|(t, _)| t.unwrap_single()) {
+                    Some(Type::Composite(name, _, _) | Type::Enum(name)) => name.clone(),
+                    Some(Type::Unknown) | None => return Type::Unknown,
+                    Some(t) => {
+                        self.error(format!("Values of type {t} have no methods"), method.location.clone());
+                        return Type::Unknown;
+                    }
+                };
+                let Some((collection_ref, sub_ref)) = find_method(&self.project.collections, &type_name, &method.value) else {
+                    self.error(format!("Type {type_name} has no method \"{}\"", method.value), method.location.clone());
+                    return Type::Unknown;
+                };
+                called = Some(sub_ref);
+                let params = sub_ref.args.iter().map(|arg| self.lookup_type(&arg.type_syntax)).collect();
+                (params, self.sub_result(collection_ref, sub_ref))
+            }
+        };
+
+        if params.len() != args.len() {
+            self.error(format!("Expected {} arguments, found {}", params.len(), args.len()), call_location);
+            return result;
+        }
+        for (param, (arg, location)) in params.iter().zip(&args) {
+            self.expect(param, arg, location.clone());
+        }
+        if let Some(called) = called {
+            self.check_generic_bindings(called, &sub_call.generics, &args);
+        }
+        result
+    }
+
+    //Generics of the called sub are bound by the generic arguments of the composites passed to it, like N by an Int<N> argument,
+    //and generics used as types by the types of the arguments. Every binding has to agree
+    fn check_generic_bindings(&mut self, sub: &SubstructureSyntax, generics: &[ExpressionSyntax], args: &[(Type, Option<CodeLocation>)]) {
+        let mut bound: HashMap<&str, usize> = sub.generics.iter().zip(generics).filter_map(|(name, value)| match value {
+            ExpressionSyntax::Int(value) => Some((name.value.as_str(), value.value)),
+            _ => None
+        }).collect();
+        let mut bound_types: HashMap<&str, &Type> = HashMap::new();
+        for (param, (arg, location)) in sub.args.iter().zip(args) {
+            if let TypeSyntax::Composite { name, .. } = &param.type_syntax {
+                if sub.is_type_generic(&name.value) {
+                    match bound_types.get(name.value.as_str()) {
+                        Some(expected) if !expected.is_compatible(arg) => {
+                            self.error(format!("Argument {} of {} expects {} = {expected}, found {arg}", param.name.value, sub.name.value, name.value), location.clone());
+                        }
+                        Some(_) => {}
+                        None => {
+                            bound_types.insert(&name.value, arg);
+                        }
+                    }
+                    continue;
+                }
+            }
+            let (TypeSyntax::Composite { name, generics }, Type::Composite(_, values, _)) = (&param.type_syntax, arg.unwrap_single()) else {
+                continue;
+            };
+            for (generic, value) in generics.iter().zip(values) {
+                let (ExpressionSyntax::Variable(generic), Some(value)) = (generic, value) else {
+                    continue;
+                };
+                if !sub.generics.iter().any(|g| g.value == generic.value) {
+                    continue;
+                }
+                match bound.get(generic.value.as_str()) {
+                    Some(expected) if expected != value => {
+                        self.error(format!("Argument {} of {} expects {} with {} = {expected}, found {value}", param.name.value, sub.name.value, name.value, generic.value), location.clone());
+                    }
+                    Some(_) => {}
+                    None => {
+                        bound.insert(&generic.value, *value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+//Collects the witnesses declared in the code, including the ones in nested blocks
+fn witness_declarations<'b>(code: &'b [CodeSyntax], witnesses: &mut Vec<&'b TypedIdentifierSyntax>) {
+    for statement in code {
+        match statement {
+            CodeSyntax::Witness { declaration } => witnesses.push(declaration),
+            CodeSyntax::For { iterator_body: body, .. } | CodeSyntax::If { condition_true: body, .. } => witness_declarations(body, witnesses),
+            CodeSyntax::IfElse { condition_true, condition_false, .. } => {
+                witness_declarations(condition_true, witnesses);
+                witness_declarations(condition_false, witnesses);
+            }
+            CodeSyntax::Match { arms, .. } => arms.iter().for_each(|arm| witness_declarations(&arm.code, witnesses)),
+            _ => {}
         }
-        todo!()
     }
 }
 
-//fn check_code_block(code: &Vec<CodeSyntax>, vars: HashMap<>)
\ No newline at end of file
+//The super constant written to by an assignment, if the assigned variable isn't shadowed by a local one
+fn assigned_super<'b>(variable: &'b ExpressionSyntax, variables: &Scope<Type>, project: &Project) -> Option<&'b LocationValue<String>> {
+    match variable {
+        ExpressionSyntax::Variable(name) if variables.get(&name.value).is_none() && project.supers.contains_key(&name.value) => Some(name),
+        ExpressionSyntax::Access { base, .. } | ExpressionSyntax::AccessIdx { base, .. } | ExpressionSyntax::IndexOp { base, .. } => assigned_super(base, variables, project),
+        _ => None
+    }
+}
+
+//Argument and result types of the super:: builtins
+fn super_signature(name: &str) -> Option<(Vec<Type>, Type)> {
+    match name {
+        "seed" => Some((vec![Type::Bool], Type::Bool)),
+        "add" | "sb" | "mul" | "div" | "mod" | "min" | "max" => Some((vec![Type::SuperInt, Type::SuperInt], Type::SuperInt)),
+        "eq" | "ne" | "gt" | "gte" | "lt" | "lte" => Some((vec![Type::SuperInt, Type::SuperInt], Type::SuperBool)),
+        "len" => Some((vec![Type::Array(Box::new(Type::Unknown))], Type::SuperInt)),
+        "concat" => Some((vec![Type::SuperString, Type::SuperString], Type::SuperString)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::{compile_source, diagnostic::{DiagnosticPipelineLocation, DiagnosticType}};
+
+    #[test]
+    fn type_mismatch() {
+        let source = r#"
+            problem {
+                sub test(bool: a) {
+                    force not 3 => true
+                }
+            }"#;
+        let output = compile_source(source);
+        assert!(output.nodes.is_empty());
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).collect();
+        let [error] = errors.as_slice() else {
+            panic!("Expected a single error, found {:?}", output.diagnostics);
+        };
+        assert!(matches!(error.pipeline_location, DiagnosticPipelineLocation::SemanticAnalysis));
+        assert_eq!(error.description, "Mismatched types: expected bool, found sup");
+        let section = error.location.as_ref().and_then(|l| l.section.as_ref()).expect("Expected the location of the argument");
+        assert_eq!(&source[section.location_begin as usize..section.location_end as usize], "3");
+
+        //Type names are case sensitive
+        let source = r#"
+            import std::int
+            problem {
+                sub test(int<4>: a) {}
+            }"#;
+        let output = compile_source(source);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).collect();
+        let [error] = errors.as_slice() else {
+            panic!("Expected a single error, found {:?}", output.diagnostics);
+        };
+        assert!(matches!(error.pipeline_location, DiagnosticPipelineLocation::SemanticAnalysis));
+        assert_eq!(error.description, "Unknown type \"int\"");
+        let section = error.location.as_ref().and_then(|l| l.section.as_ref()).expect("Expected the location of the type name");
+        assert_eq!(&source[section.location_begin as usize..section.location_end as usize], "int");
+    }
+
+    #[test]
+    fn generic_width_mismatch() {
+        let output = compile_source(r#"
+            import std::int
+            problem {
+                sub test(bool: a) {
+                    let x = int::from_super(5, 4)
+                    let y = int::from_super(2, 3)
+                    let s = int::add(x, y)
+                }
+            }"#);
+        assert!(output.diagnostics.iter().any(|d| d.description.contains("Argument b of add expects Int with N = 4, found 3")), "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn generic_argument_mismatch() {
+        let output = compile_source(r#"
+            import std::int
+            collection c {
+                sub low(Int<3>: a) {} = a.data[0]
+            }
+            problem {
+                sub test(Int<3>: x, Int<4>: y) {
+                    let s = int::add(x, y)
+                    let b = c::low(y)
+                    let t = int::add(x, x)
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).collect();
+        assert_eq!(errors.len(), 2, "{:?}", output.diagnostics);
+        assert!(errors.iter().all(|d| matches!(d.pipeline_location, DiagnosticPipelineLocation::SemanticAnalysis)));
+        assert_eq!(errors[0].description, "Argument b of add expects Int with N = 3, found 4");
+        assert_eq!(errors[1].description, "Mismatched types: expected Int<3>, found Int<4>");
+    }
+
+    #[test]
+    fn destructuring_arity_mismatch() {
+        let output = compile_source(r#"
+            problem {
+                sub test(bool: a, bool: b) {
+                    let (s, c, d) = (a, b)
+                }
+            }"#);
+        let diagnostic = output.diagnostics.iter().find(|d| d.description.contains("into a tuple of 3 elements")).expect("Expected an arity error");
+        assert!(diagnostic.location.as_ref().is_some_and(|l| l.section.is_some()), "{:?}", diagnostic);
+    }
+
+    #[test]
+    fn optional_fields() {
+        let output = compile_source(r#"
+            composite Sum {
+                bool: value,
+                bool: carry = false,
+                bool: extra?
+            }
+            problem {
+                sub test(bool: a) {
+                    let s = Sum { value: a, extra: not a }
+                    output (s.value, s.carry, s.extra)
+                }
+            }
+            solution {
+                test(true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["(True, False, False, )".to_string()]);
+
+        let output = compile_source(r#"
+            composite Sum {
+                bool: value,
+                bool: extra?
+            }
+            problem {
+                sub test(bool: a) {
+                    let s = Sum { value: a }
+                    output s.extra
+                    let Sum { extra: e } = s
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| d.description == "Optional field \"extra\" of Sum wasn't assigned").collect();
+        assert_eq!(errors.len(), 2, "{:?}", output.diagnostics);
+        assert!(errors.iter().all(|d| matches!(d.pipeline_location, DiagnosticPipelineLocation::SemanticAnalysis)));
+    }
+
+    #[test]
+    fn generic_field_defaults() {
+        let output = compile_source(r#"
+            composite Buf<N> {
+                [bool; N]: data,
+                sup: len = N
+            }
+            problem {
+                sub test(bool: a) {
+                    let b = Buf { data: [a, not a, a] }
+                    output b.len
+                    let c = Buf { data: [a] }
+                    output c.len
+                }
+            }
+            solution {
+                test(true)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["3".to_string(), "1".to_string()]);
+    }
+
+    #[test]
+    fn invalid_witnesses() {
+        let output = compile_source(r#"
+            import std::int
+            problem {
+                sub find(bool: a) {
+                    witness [bool; 4]: sized
+                    witness Int<4>: number
+                    witness [bool]: bits
+                    witness Int: unsized_number
+                    for (i in 2) {
+                        witness bool: repeated
+                    }
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().map(|d| d.description.as_str()).filter(|d| d.starts_with("Witness")).collect();
+        assert_eq!(errors, vec![
+            "Witness bits needs a type of known size, like [bool; 8] or Int<8>",
+            "Witness unsized_number needs a type of known size, like [bool; 8] or Int<8>",
+            "Witness repeated can't be declared in a loop",
+        ], "{:?}", output.diagnostics);
+    }
+
+    #[test]
+    fn reference_calls() {
+        let output = compile_source(r#"
+            import std::array
+            collection c {
+                sub flip(bool: x) {} = not x
+                sub count(sup: n, bool: x) {} = super::add(n, 1)
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    let f = sub c::flip
+                    force f(a, b) => true
+                    force f(3) => true
+                    let y = f(a)
+                    output y.data
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).map(|d| d.description.as_str()).collect();
+        assert_eq!(errors, vec![
+            "Expected 1 arguments, found 2",
+            "Mismatched types: expected bool, found sup",
+            "Tried to access field \"data\" on a value of type bool, which doesn't have fields.",
+        ], "{:?}", output.diagnostics);
+
+        //The initial value of fold can have any type
+        let output = compile_source(r#"
+            import std::array
+            collection c {
+                sub count(sup: n, bool: x) {} = super::add(n, 1)
+            }
+            problem {
+                sub test(bool: a, bool: b) {
+                    output array::fold([a, b], 0, sub c::count)
+                }
+            }
+            solution {
+                test(true, false)
+            }"#);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["2".to_string()]);
+    }
+
+    #[test]
+    fn type_generics() {
+        let output = compile_source(r#"
+            collection c {
+                sub pick<T>(bool: condition, T: a, T: b) {
+                    let res = b
+                    if (condition) {
+                        res = a
+                    }
+                } = res
+            }
+            problem {
+                sub test(bool: a) {
+                    output c::pick(true, 1, 2)
+                    output c::pick(a, true, 2)
+                }
+            }"#);
+        let errors: Vec<_> = output.diagnostics.iter().filter(|d| matches!(d.diagnostic_type, DiagnosticType::Error)).collect();
+        assert_eq!(errors.len(), 1, "{:?}", output.diagnostics);
+        assert_eq!(errors[0].description, "Argument b of pick expects T = bool, found sup");
+        assert!(matches!(errors[0].pipeline_location, DiagnosticPipelineLocation::SemanticAnalysis));
+    }
+}
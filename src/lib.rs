@@ -1,7 +1,10 @@
 pub mod compiler;
+pub mod wasm;
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+
     use crate::compiler::{self, settings::Settings};
 
     #[test]
@@ -9,4 +12,15 @@ mod tests {
         let settings = Settings::default();
         compiler::compile(&settings);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn cmp_in_memory() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("main.coala"), "import gates\nproblem {\n    sub test(bool: a, bool: b) {\n        output a\n        force gates::nand(a, b) => true\n    }\n}\nsolution {\n    test(true, false)\n}".to_string());
+        files.insert(PathBuf::from("gates.coala"), "collection gates {\n    sub nand(bool: a, bool: b) {} = or(not a, not b)\n}".to_string());
+        let output = compiler::compile_in_memory(&Settings::default(), &files);
+        assert!(output.is_error_free(), "{:?}", output.diagnostics);
+        assert_eq!(output.outputs, vec!["True".to_string()]);
+        assert!(!output.nodes.is_empty());
+    }
+}
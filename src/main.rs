@@ -1,11 +1,13 @@
 pub mod compiler;
 
 use std::env::args;
-use compiler::settings::Settings;
+use compiler::{export::ExportFormat, settings::Settings};
 
-fn parse_args() -> Settings {
+fn parse_args() -> Result<Settings, String> {
     let mut args = args();
     let mut settings = Settings::default();
+    //The formats given with --emit replace the default one
+    let mut emit = vec![];
     //Consume path of executable
     args.next();
 
@@ -28,14 +30,42 @@ fn parse_args() -> Settings {
             "-o" => {
                 settings.output_directory = args.next();
             }
+            "--name" => {
+                if let Some(name) = args.next() {
+                    settings.output_name = name;
+                }
+            }
             "--dev" => {
                 settings.print_debug_logs = true;
             }
+            "--solve" => {
+                settings.solve = true;
+            }
+            "--emit" => {
+                let name = args.next().ok_or_else(|| format!("Expected an export format after --emit ({})", ExportFormat::NAMES.join(", ")))?;
+                let format = ExportFormat::from_name(&name).ok_or_else(|| format!("Unknown export format \"{name}\", expected one of {}", ExportFormat::NAMES.join(", ")))?;
+                if !emit.contains(&format) {
+                    emit.push(format);
+                }
+            }
+            "--decode-model" => {
+                settings.decode_model = args.next();
+            }
+            "-D" => {
+                let definition = args.next();
+                match definition.as_deref().and_then(|d| d.split_once('=')) {
+                    Some((name, value)) => settings.super_overrides.push((name.to_owned(), value.to_owned())),
+                    None => println!("Expected a super definition after -D, like -D INT_LENGTH=32")
+                }
+            }
             _ => {}
         }
     }
+    if !emit.is_empty() {
+        settings.emit = emit;
+    }
 
-    settings
+    Ok(settings)
 }
 
 fn main() {
@@ -50,7 +80,13 @@ fn main() {
             ..Default::default()
         }
     } else {
-        parse_args()
+        match parse_args() {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
     };
     
     compiler::compile(&settings);
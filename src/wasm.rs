@@ -0,0 +1,142 @@
+/*
+Bindings for running the compiler from JavaScript. Results are handed over as JSON strings, so the browser side doesn't depend on any generated types.
+*/
+
+use std::{collections::HashMap, path::PathBuf};
+
+use wasm_bindgen::prelude::*;
+
+use crate::compiler::{self, atom_tree::AtomRoot, atom_tree_to_graph::{Label, Node}, diagnostic::{Diagnostic, DiagnosticPipelineLocation, DiagnosticType}, export, settings::Settings, CompileOutput};
+
+///Compiles a single Coala source as `main.coala`. The standard library can be imported as usual.
+#[wasm_bindgen]
+pub fn compile(source: &str) -> String {
+    compile_with_options(source, false, false)
+}
+
+///Compiles a single Coala source as `main.coala`, optionally optimizing the IR and searching a coloring for the unlabelled nodes.
+#[wasm_bindgen]
+pub fn compile_with_options(source: &str, optimize: bool, solve: bool) -> String {
+    let mut files = HashMap::new();
+    files.insert(PathBuf::from("main.coala"), source.to_string());
+    compile_files(&files, optimize, solve)
+}
+
+///Compiles a set of files, where `paths[i]` (e.g. `folder/file.coala`) holds `sources[i]`. One of the paths has to be `main.coala`.
+#[wasm_bindgen]
+pub fn compile_project(paths: Vec<String>, sources: Vec<String>, optimize: bool, solve: bool) -> String {
+    if paths.len() != sources.len() {
+        let description = format!("Got {} paths, but {} sources", paths.len(), sources.len());
+        let diagnostic = Diagnostic::new(DiagnosticType::Error, description, None, DiagnosticPipelineLocation::IO);
+        let output = CompileOutput { nodes: vec![], atom_tree: AtomRoot::default(), diagnostics: vec![diagnostic], outputs: vec![], further_solutions: vec![] };
+        return to_json(&output);
+    }
+    let files = paths.into_iter().map(PathBuf::from).zip(sources).collect();
+    compile_files(&files, optimize, solve)
+}
+
+fn compile_files(files: &HashMap<PathBuf, String>, optimize: bool, solve: bool) -> String {
+    let settings = Settings { color: false, optimize, solve, base_path: None, ..Default::default() };
+    let output = compiler::compile_in_memory(&settings, files);
+    to_json(&output)
+}
+
+//The fields of the first solution are also given at the top level
+fn to_json(output: &CompileOutput) -> String {
+    let mut json = String::from("{");
+    json.push_str(&format!("\"success\":{},", output.is_error_free()));
+    json.push_str(&graph_fields(&output.nodes, &output.outputs));
+
+    let diagnostics: Vec<String> = output.diagnostics.iter().map(diagnostic_to_json).collect();
+    json.push_str(&format!(",\"diagnostics\":[{}],", diagnostics.join(",")));
+
+    let mut solutions = vec![];
+    if !output.nodes.is_empty() {
+        solutions.push(format!("{{{}}}", graph_fields(&output.nodes, &output.outputs)));
+    }
+    solutions.extend(output.further_solutions.iter().map(|solution| format!("{{{}}}", graph_fields(&solution.nodes, &solution.outputs))));
+    json.push_str(&format!("\"solutions\":[{}]", solutions.join(",")));
+    json.push('}');
+    json
+}
+
+//The graph of one solution and the text it outputs, without the surrounding braces
+fn graph_fields(nodes: &[Node], outputs: &[String]) -> String {
+    let mut json = format!("\"node_count\":{},", nodes.len());
+
+    let edges: Vec<String> = export::edges(nodes).iter().map(|(a, b)| format!("[{a},{b}]")).collect();
+    json.push_str(&format!("\"edges\":[{}],", edges.join(",")));
+
+    let labels: Vec<String> = nodes.iter().map(|node| quote(export::label_name(node.label))).collect();
+    json.push_str(&format!("\"labels\":[{}],", labels.join(",")));
+
+    let whitelists: Vec<String> = nodes.iter().map(|node| {
+        let labels: Vec<String> = [Label::True, Label::False, Label::Neutral].into_iter()
+            .filter(|label| node.label_whitelist.contains(label))
+            .map(|label| quote(export::label_name(label)))
+            .collect();
+        format!("[{}]", labels.join(","))
+    }).collect();
+    json.push_str(&format!("\"whitelists\":[{}],", whitelists.join(",")));
+
+    let outputs: Vec<String> = outputs.iter().map(|o| quote(o)).collect();
+    json.push_str(&format!("\"outputs\":[{}]", outputs.join(",")));
+    json
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> String {
+    let mut json = format!("{{\"type\":{},\"message\":{}", quote(&format!("{:?}", diagnostic.diagnostic_type)), quote(&diagnostic.description));
+    if let Some(location) = &diagnostic.location {
+        json.push_str(&format!(",\"file\":{}", quote(&location.path.to_string_lossy())));
+        if let Some(section) = &location.section {
+            json.push_str(&format!(",\"begin\":{},\"end\":{}", section.location_begin, section.location_end));
+        }
+    }
+    json.push('}');
+    json
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::code_location::{CodeLocation, Section};
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("say \"hi\"\\n"), "\"say \\\"hi\\\"\\\\n\"");
+        assert_eq!(quote("a\nb\tc\r"), "\"a\\nb\\tc\\r\"");
+        assert_eq!(quote("\u{1}\u{1f}é"), "\"\\u0001\\u001fé\"");
+    }
+
+    #[test]
+    fn builds_json() {
+        let nodes = vec![
+            Node { connections: vec![1], label: Label::True, label_whitelist: [Label::True].into_iter().collect() },
+            Node { connections: vec![0], label: Label::Null, label_whitelist: [Label::True, Label::False].into_iter().collect() },
+        ];
+        let location = CodeLocation { path: PathBuf::from("main.coala"), section: Some(Section { location_begin: 3, location_end: 7 }) };
+        let diagnostic = Diagnostic::new(DiagnosticType::Warning, "Unused \"x\"".to_string(), Some(location), DiagnosticPipelineLocation::SemanticAnalysis);
+        let output = CompileOutput { nodes, atom_tree: AtomRoot::default(), diagnostics: vec![diagnostic], outputs: vec!["line\nbreak".to_string()], further_solutions: vec![] };
+        let graph = r#""node_count":2,"edges":[[0,1]],"labels":["TRUE","NULL"],"whitelists":[["TRUE"],["TRUE","FALSE"]],"outputs":["line\nbreak"]"#;
+        let diagnostics = r#""diagnostics":[{"type":"Warning","message":"Unused \"x\"","file":"main.coala","begin":3,"end":7}]"#;
+        assert_eq!(to_json(&output), format!("{{\"success\":true,{graph},{diagnostics},\"solutions\":[{{{graph}}}]}}"));
+    }
+}
@@ -0,0 +1,59 @@
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn compiles_source_to_json() {
+    let json = coala::wasm::compile("problem {\n    sub test(bool: a) {\n        force not a => true\n    }\n}\nsolution {\n    test(false)\n}");
+    assert!(json.starts_with("{\"success\":true,"), "{json}");
+    let labels = array_field(&json, "labels");
+    let node_count: usize = json["{\"success\":true,\"node_count\":".len()..].split(',').next().and_then(|n| n.parse().ok()).expect("Expected a node count");
+    assert_eq!(labels.len(), node_count, "{json}");
+    for label in ["\"TRUE\"", "\"FALSE\"", "\"NEUTRAL\""] {
+        assert!(labels.contains(&label), "{json}");
+    }
+}
+
+//The elements of the first array with the given name, which may not contain nested arrays
+fn array_field<'a>(json: &'a str, name: &str) -> Vec<&'a str> {
+    let start = json.find(&format!("\"{name}\":[")).expect("Expected the field") + name.len() + 4;
+    let end = start + json[start..].find(']').expect("Expected the end of the array");
+    json[start..end].split(',').filter(|item| !item.is_empty()).collect()
+}
+
+#[wasm_bindgen_test]
+fn reports_diagnostics() {
+    let json = coala::wasm::compile("import missing");
+    assert!(json.starts_with("{\"success\":false,"), "{json}");
+    assert!(json.contains("\"type\":\"Error\""), "{json}");
+}
+
+#[wasm_bindgen_test]
+fn imports_std() {
+    let json = coala::wasm::compile("import std::bool\nproblem {\n    sub test(bool: a, bool: b) {\n        output bool::xor(a, b)\n    }\n}\nsolution {\n    test(true, false)\n}");
+    assert!(json.starts_with("{\"success\":true,"), "{json}");
+    assert!(json.contains("\"True\""), "{json}");
+}
+
+#[wasm_bindgen_test]
+fn reports_malformed_source() {
+    let json = coala::wasm::compile("problem {\n    sub test(bool: a {\n");
+    assert!(json.starts_with("{\"success\":false,"), "{json}");
+    assert!(json.contains("\"type\":\"Error\""), "{json}");
+}
+
+#[wasm_bindgen_test]
+fn reports_mismatched_project_files() {
+    let json = coala::wasm::compile_project(vec!["main.coala".to_string(), "other.coala".to_string()], vec![String::new()], false, false);
+    assert!(json.starts_with("{\"success\":false,"), "{json}");
+    assert!(json.contains("Got 2 paths, but 1 sources"), "{json}");
+}
+
+#[wasm_bindgen_test]
+fn lists_every_solution() {
+    let json = coala::wasm::compile("problem {\n    sub test(bool: a) {\n        output a\n    }\n}\nsolution {\n    test(true),\n    test(false)\n}");
+    assert!(json.starts_with("{\"success\":true,"), "{json}");
+    let solutions = &json[json.find("\"solutions\":[").expect("Expected a solutions array")..];
+    assert_eq!(solutions.matches("\"node_count\":").count(), 2, "{json}");
+    assert!(solutions.contains("\"outputs\":[\"True\"]}") && solutions.contains("\"outputs\":[\"False\"]}"), "{json}");
+}